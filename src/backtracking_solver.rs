@@ -0,0 +1,492 @@
+use crate::board::{BoardIndexable, NextToPolicy, empty_board};
+use crate::linear_programming_solvers::{Constraint, ConstraintSet, ConstraitType, ExtraRow, PreparedSet, SolverBackend};
+use crate::error::SolverError;
+
+use std::collections::HashMap;
+
+// Solves the sets without HiGHS: cells are decided one at a time in reading order, safe first, and every rule that
+// touches a decided cell is checked straight away so a dead end is left as early as possible.
+pub struct BacktrackingBackend;
+
+// A sum of cells times their factors that has to land in one of the allowed ranges
+#[derive(Clone)]
+struct Row {
+    terms: Vec<(usize, i64)>,
+    allowed: Vec<(i64, i64)>,
+}
+
+// The cells a rule about neighbouring cells looks at. A node is a cell of the set, or None for a cell that is
+// already known to be in the region, like a placed mine.
+#[derive(Clone)]
+struct Graph {
+    nodes: Vec<Option<usize>>,
+    neighbours: Vec<Vec<usize>>,
+    on_edge: Vec<bool>,
+}
+
+// Rules that are not a plain sum. Each one is checked against the cells decided so far, and only fails
+// once no way of deciding the rest can meet it.
+#[derive(Clone)]
+enum Check {
+    Connected { graph: Graph, count_safe: bool, to_edge: bool },
+    Dominoes(Graph),
+    Snake(Graph),
+    Eyesight { cells: Vec<usize>, weights: Vec<i64>, per_direction: [usize; 4], value: i64 },
+    Patterns { cells: Vec<usize>, masks: Vec<usize> },
+}
+
+#[derive(Clone)]
+struct Search {
+    rows: Vec<Row>,
+    checks: Vec<Check>,
+    rows_of: Vec<Vec<usize>>,
+    checks_of: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    trail: Vec<usize>,
+}
+
+fn build_graph(constraint:&Constraint, lookup:&HashMap<usize, usize>, width:usize, height:usize, policy:NextToPolicy) -> Graph {
+    let grid = empty_board(width, height);
+
+    let ids: Vec<usize> = constraint.cells.iter().chain(constraint.cells2.iter()).cloned().collect();
+    let node_of: HashMap<usize, usize> = ids.iter().enumerate().map(|(node, id)| (*id, node)).collect();
+
+    Graph {
+        nodes: ids.iter().map(|id| lookup.get(id).cloned()).collect(),
+        neighbours: ids.iter().map(|id| grid.get_next_to(*id, policy).iter().filter_map(|other| node_of.get(other).cloned()).collect()).collect(),
+        on_edge: ids.iter().map(|id| *id < width || *id >= width*(height-1) || id % width == 0 || (id+1) % width == 0).collect(),
+    }
+}
+
+impl Graph {
+    // Whether the node is a mine, None while its cell is undecided
+    fn mine(&self, values:&[Option<bool>], node:usize) -> Option<bool> {
+        match self.nodes[node] {
+            Some(index) => values[index],
+            None => Some(true)
+        }
+    }
+
+    // Mines and undecided cells around the node
+    fn around(&self, values:&[Option<bool>], node:usize) -> (usize, usize) {
+        self.neighbours[node].iter().fold((0, 0), |(mines, undecided), other| match self.mine(values, *other) {
+            Some(true) => (mines + 1, undecided),
+            Some(false) => (mines, undecided),
+            None => (mines, undecided + 1)
+        })
+    }
+}
+
+fn row_for(constraint:&Constraint, lookup:&HashMap<usize, usize>) -> Row {
+    let factored = |cells:&Vec<usize>, factor:i64| cells.iter().map(|id| (*lookup.get(id).unwrap(), factor)).collect::<Vec<(usize, i64)>>();
+    let value = constraint.value;
+
+    match constraint.constrait_type {
+        ConstraitType::Equality => Row { terms: factored(&constraint.cells, 1), allowed: vec![(value, value)] },
+        ConstraitType::Minimum => Row { terms: factored(&constraint.cells, 1), allowed: vec![(value, i64::MAX)] },
+        ConstraitType::Maximum => Row { terms: factored(&constraint.cells, 1), allowed: vec![(i64::MIN, value)] },
+        ConstraitType::BlackWhiteEquality => {
+            let mut terms = factored(&constraint.cells, 1);
+            terms.extend(factored(&constraint.cells2, 2));
+            Row { terms, allowed: vec![(value, value)] }
+        },
+        // The clue is one more or one less than the mines around it
+        ConstraitType::OffByOne => Row { terms: factored(&constraint.cells, 1), allowed: vec![(value - 1, value - 1), (value + 1, value + 1)] },
+        // Black mines minus white mines, with the placed ones in the bias, is the clue either way round
+        ConstraitType::DifferenceOfColors(black_bias) => {
+            let mut terms = factored(&constraint.cells, 1);
+            terms.extend(factored(&constraint.cells2, -1));
+            let bias = black_bias.round() as i64;
+            Row { terms, allowed: vec![(value - bias, value - bias), (-value - bias, -value - bias)] }
+        },
+        ConstraitType::SameCount => {
+            let mut terms = factored(&constraint.cells, 1);
+            terms.extend(factored(&constraint.cells2, -1));
+            Row { terms, allowed: vec![(value, value)] }
+        },
+        _ => panic!("{} is not a sum of cells", constraint.constrait_type)
+    }
+}
+
+fn check_for(constraint:&Constraint, lookup:&HashMap<usize, usize>, width:usize, height:usize) -> Check {
+    match constraint.constrait_type {
+        ConstraitType::Connected(policy) => Check::Connected { graph: build_graph(constraint, lookup, width, height, policy), count_safe: false, to_edge: false },
+        ConstraitType::ConnectedSafe(policy) => Check::Connected { graph: build_graph(constraint, lookup, width, height, policy), count_safe: true, to_edge: false },
+        ConstraitType::ConnectedToEdge(policy) => Check::Connected { graph: build_graph(constraint, lookup, width, height, policy), count_safe: false, to_edge: true },
+        ConstraitType::Dominoes => Check::Dominoes(build_graph(constraint, lookup, width, height, NextToPolicy::XSmall)),
+        ConstraitType::Snake => Check::Snake(build_graph(constraint, lookup, width, height, NextToPolicy::XSmall)),
+        ConstraitType::Eyesight(per_direction) => Check::Eyesight {
+            cells: constraint.cells.iter().map(|id| *lookup.get(id).unwrap()).collect(),
+            weights: constraint.payload.iter().map(|weight| *weight as i64).collect(),
+            per_direction,
+            value: constraint.value,
+        },
+        ConstraitType::OneOfPatterns => Check::Patterns {
+            cells: constraint.cells.iter().map(|id| *lookup.get(id).unwrap()).collect(),
+            masks: constraint.payload.clone(),
+        },
+        _ => panic!("{} is a sum of cells", constraint.constrait_type)
+    }
+}
+
+impl Check {
+    fn cells(&self) -> Vec<usize> {
+        match self {
+            Check::Connected { graph, .. } | Check::Dominoes(graph) | Check::Snake(graph) => graph.nodes.iter().filter_map(|node| *node).collect(),
+            Check::Eyesight { cells, .. } | Check::Patterns { cells, .. } => cells.clone(),
+        }
+    }
+
+    // False once the decided cells break the rule whatever the rest turn out to be
+    fn holds(&self, values:&[Option<bool>]) -> bool {
+        match self {
+            Check::Connected { graph, count_safe, to_edge } => {
+                // Undecided cells may still join the region up, so the cells already in it only have to reach
+                // each other, or the edge, through cells that are in the region or undecided
+                let in_region: Vec<Option<bool>> = (0..graph.nodes.len()).map(|node| match graph.nodes[node] {
+                    Some(index) => values[index].map(|mine| mine != *count_safe),
+                    None => Some(true)
+                }).collect();
+                let passable = |node:usize| in_region[node] != Some(false);
+
+                let mut reached = vec![false; graph.nodes.len()];
+                let mut stack: Vec<usize> = if *to_edge {
+                    (0..graph.nodes.len()).filter(|node| passable(*node) && graph.on_edge[*node]).collect()
+                } else {
+                    (0..graph.nodes.len()).find(|node| in_region[*node] == Some(true)).into_iter().collect()
+                };
+                for node in &stack {
+                    reached[*node] = true;
+                }
+
+                while let Some(node) = stack.pop() {
+                    for other in &graph.neighbours[node] {
+                        if passable(*other) && !reached[*other] {
+                            reached[*other] = true;
+                            stack.push(*other);
+                        }
+                    }
+                }
+
+                (0..graph.nodes.len()).all(|node| in_region[node] != Some(true) || reached[node])
+            },
+            // Every mine touches exactly one other mine, the other half of its domino
+            Check::Dominoes(graph) => (0..graph.nodes.len()).all(|node| {
+                if graph.mine(values, node) != Some(true) {
+                    return true;
+                }
+                let (mines, undecided) = graph.around(values, node);
+                mines <= 1 && mines + undecided >= 1
+            }),
+            // Every mine touches one or two other mines, and exactly two of them, the ends, touch only one
+            Check::Snake(graph) => {
+                let mut ends = 0;
+                let mut all_decided = true;
+
+                for node in 0..graph.nodes.len() {
+                    match graph.mine(values, node) {
+                        Some(true) => {
+                            let (mines, undecided) = graph.around(values, node);
+                            if mines > 2 || mines + undecided == 0 {
+                                return false;
+                            }
+                            if mines == 1 && undecided == 0 {
+                                ends += 1;
+                            }
+                            all_decided &= undecided == 0;
+                        },
+                        Some(false) => (),
+                        None => all_decided = false
+                    }
+                }
+
+                ends <= 2 && (!all_decided || ends == 2)
+            },
+            // The cells seen in each direction run up to the first mine, so the clue has to lie between the cells
+            // seen if every undecided cell is a mine and the cells seen if every undecided cell is safe
+            Check::Eyesight { cells, weights, per_direction, value } => {
+                let (mut fewest, mut most) = (0, 0);
+                let mut offset = 0;
+
+                for count in per_direction {
+                    let (mut blocked_fewest, mut blocked_most) = (false, false);
+                    for i in offset..offset+count {
+                        match values[cells[i]] {
+                            Some(false) => {
+                                if !blocked_fewest { fewest += weights[i] };
+                                if !blocked_most { most += weights[i] };
+                            },
+                            Some(true) => {
+                                blocked_fewest = true;
+                                blocked_most = true;
+                            },
+                            None => {
+                                blocked_fewest = true;
+                                if !blocked_most { most += weights[i] };
+                            }
+                        }
+                    }
+                    offset += count;
+                }
+
+                fewest <= *value && *value <= most
+            },
+            Check::Patterns { cells, masks } => masks.iter().any(|mask| {
+                cells.iter().enumerate().all(|(bit, index)| values[*index].is_none_or(|mine| mine == ((mask >> bit) & 1 == 1)))
+            }),
+        }
+    }
+}
+
+impl Search {
+    fn new(constraints:&ConstraintSet, selected:&[&Constraint]) -> Search {
+        let mut lookup: HashMap<usize, usize> = HashMap::new();
+        for i in 0..constraints.cells.len() {
+            lookup.insert( constraints.cells[i], i);
+        }
+
+        let mut rows = vec![];
+        let mut checks = vec![];
+        for constraint in selected {
+            match constraint.constrait_type {
+                ConstraitType::Equality | ConstraitType::Minimum | ConstraitType::Maximum | ConstraitType::BlackWhiteEquality
+                    | ConstraitType::OffByOne | ConstraitType::DifferenceOfColors(_) | ConstraitType::SameCount => rows.push(row_for(constraint, &lookup)),
+                _ => checks.push(check_for(constraint, &lookup, constraints.width, constraints.height))
+            }
+        }
+        let mut rows_of = vec![vec![]; constraints.cells.len()];
+        for (r, row) in rows.iter().enumerate() {
+            for (index, _) in &row.terms {
+                rows_of[*index].push(r);
+            }
+        }
+        let mut checks_of = vec![vec![]; constraints.cells.len()];
+        for (c, check) in checks.iter().enumerate() {
+            for index in check.cells() {
+                checks_of[index].push(c);
+            }
+        }
+
+        Search {
+            rows,
+            checks,
+            rows_of,
+            checks_of,
+            values: vec![None; constraints.cells.len()],
+            trail: vec![],
+        }
+    }
+
+    fn add_row(&mut self, row:&ExtraRow) {
+        for (index, _) in &row.cells {
+            self.rows_of[*index].push(self.rows.len());
+        }
+        self.rows.push(Row { terms: row.cells.clone(), allowed: vec![(row.min, row.max)] });
+    }
+
+    fn set(&mut self, index:usize, mine:bool) {
+        self.values[index] = Some(mine);
+        self.trail.push(index);
+    }
+
+    fn undo(&mut self, mark:usize) {
+        while self.trail.len() > mark {
+            let index = self.trail.pop().unwrap();
+            self.values[index] = None;
+        }
+    }
+
+    // Narrows the row down to the cells decided so far, and decides every cell that can only go one way.
+    // Returns false when the row can no longer be met.
+    fn propagate_row(&mut self, r:usize, changed:&mut Vec<usize>) -> bool {
+        let (mut low, mut high) = (0, 0);
+        for (index, factor) in &self.rows[r].terms {
+            match self.values[*index] {
+                Some(mine) => {
+                    low += factor * mine as i64;
+                    high += factor * mine as i64;
+                },
+                None => {
+                    low += (*factor).min(0);
+                    high += (*factor).max(0);
+                }
+            }
+        }
+
+        let fits = |row:&Row, low:i64, high:i64| row.allowed.iter().any(|(min, max)| low <= *max && *min <= high);
+
+        if !fits(&self.rows[r], low, high) {
+            return false;
+        }
+
+        for t in 0..self.rows[r].terms.len() {
+            let (index, factor) = self.rows[r].terms[t];
+            if self.values[index].is_some() {
+                continue;
+            }
+
+            // The range of the row once this cell is decided as well
+            let with = |low:i64, high:i64, mine:bool| (low - factor.min(0) + factor * mine as i64, high - factor.max(0) + factor * mine as i64);
+            let possible: Vec<bool> = [false, true].into_iter().filter(|mine| {
+                let (low, high) = with(low, high, *mine);
+                fits(&self.rows[r], low, high)
+            }).collect();
+
+            match possible[..] {
+                [] => return false,
+                [mine] => {
+                    (low, high) = with(low, high, mine);
+                    self.set(index, mine);
+                    changed.push(index);
+                },
+                _ => ()
+            }
+        }
+
+        true
+    }
+
+    // Follows the cells that were just decided through every rule they are in
+    fn propagate(&mut self, mut changed:Vec<usize>) -> bool {
+        while let Some(index) = changed.pop() {
+            for k in 0..self.rows_of[index].len() {
+                if !self.propagate_row(self.rows_of[index][k], &mut changed) {
+                    return false;
+                }
+            }
+            for c in &self.checks_of[index] {
+                if !self.checks[*c].holds(&self.values) {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    fn start(&mut self) -> bool {
+        let mut changed = vec![];
+        for r in 0..self.rows.len() {
+            if !self.propagate_row(r, &mut changed) {
+                return false;
+            }
+        }
+        if !self.checks.iter().all(|check| check.holds(&self.values)) {
+            return false;
+        }
+
+        self.propagate(changed)
+    }
+
+    fn search(&mut self, from:usize) -> bool {
+        let index = match (from..self.values.len()).find(|i| self.values[*i].is_none()) {
+            Some(index) => index,
+            None => return true
+        };
+
+        for mine in [false, true] {
+            let mark = self.trail.len();
+            self.set(index, mine);
+            if self.propagate(vec![index]) && self.search(index + 1) {
+                return true;
+            }
+            self.undo(mark);
+        }
+
+        false
+    }
+}
+
+// The rows and checks of a set with nothing decided yet. Each solve starts from a copy with the extra rows added.
+struct BacktrackingPreparedSet {
+    search: Search,
+}
+
+impl PreparedSet for BacktrackingPreparedSet {
+    fn find_layout(&self, extra:&[ExtraRow]) -> Result<Option<Vec<bool>>, SolverError> {
+        let mut search = self.search.clone();
+        for row in extra {
+            search.add_row(row);
+        }
+
+        // The search runs until it has tried every way, so running out of ways is a proof
+        if search.start() && search.search(0) {
+            Ok(Some(search.values.iter().map(|value| value.unwrap()).collect()))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl SolverBackend for BacktrackingBackend {
+    fn prepare(&self, constraints:&ConstraintSet, selected:&[&Constraint]) -> Box<dyn PreparedSet> {
+        Box::new(BacktrackingPreparedSet { search: Search::new(constraints, selected) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Board, MinesweeperCell};
+    use crate::linear_programming_solvers::{HighsBackend, find_known_squares, count_solutions};
+    use crate::plus_linear_solvers::Variant;
+
+    // Small boards with clues, walls and mines scattered at random. Most of them have no layout at all,
+    // so both the proofs and the contradictions get compared.
+    fn random_boards(count:usize, seed:u64) -> Vec<Board> {
+        let mut state = seed;
+        let mut next = |limit:u64| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) % limit
+        };
+
+        (0..count).map(|_| {
+            let rows = (0..9).map(|_| match next(10) {
+                0..=5 => MinesweeperCell::Empty,
+                6 => MinesweeperCell::Mine,
+                7 => MinesweeperCell::Wall([next(3) as usize, 0, 0, 0]),
+                _ => MinesweeperCell::Number(next(4) as usize),
+            }).collect();
+            let total_mines = Some(2 + next(4) as usize);
+            Board {rows, width:3, height:3, total_mines, variant:None}
+        }).collect()
+    }
+
+    #[test]
+    fn matches_highs() {
+        let codes = ["V", "Q", "C", "T", "O", "D", "S", "B", "M", "L", "W", "N", "X", "P", "E", "X'", "K", "W'"];
+
+        for (seed, code) in codes.iter().enumerate() {
+            let variant = Variant::from_code(code).unwrap();
+            let constraint_set = |board:&Board| variant.constraint_set(board);
+
+            for board in random_boards(10, seed as u64) {
+                let highs = find_known_squares(&board, constraint_set, &HighsBackend);
+                let native = find_known_squares(&board, constraint_set, &BacktrackingBackend);
+                match (highs, native) {
+                    (Ok(highs), Ok(native)) => {
+                        assert_eq!(highs.mines, native.mines, "{}\n{}", code, board);
+                        assert_eq!(highs.safe, native.safe, "{}\n{}", code, board);
+                    },
+                    (highs, native) => assert_eq!(highs.err(), native.err(), "{}\n{}", code, board)
+                }
+
+                assert_eq!(count_solutions(&board, constraint_set, &HighsBackend, 50), count_solutions(&board, constraint_set, &BacktrackingBackend, 50), "{}\n{}", code, board);
+            }
+        }
+    }
+
+    #[test]
+    fn solves_full_size_connected() {
+        // Only a few 0s are open, so the search has to leave regions that can no longer join up long before every
+        // cell is decided. Besides the squares next to the 0s, the two squares cut off near the bottom cannot hold
+        // ten connected mines.
+        let board: Board = "C 8x8 10\n0      0\n        \n        \n        \n      0 \n    0   \n  0     \n0      0\n".parse().unwrap();
+        let variant = board.variant.unwrap();
+
+        let known = find_known_squares(&board, |board| variant.constraint_set(board), &BacktrackingBackend).unwrap();
+        assert!(known.mines.is_empty());
+        assert_eq!(known.safe.len(), 32);
+        assert!(known.safe.contains(&60) && known.safe.contains(&61));
+    }
+}
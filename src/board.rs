@@ -1,10 +1,13 @@
-use std::{fmt, char::from_digit, ops::Index};
+use std::{fmt, char::from_digit, ops::Index, str::FromStr};
+
+use crate::plus_linear_solvers::Variant;
 
 #[derive(Copy)]
 #[derive(Clone)]
 #[derive(PartialEq, Eq)]
 pub enum MinesweeperCell {
     Number(usize),
+    Wall([usize; 4]),
     Empty,
     Question,
     Mine
@@ -16,16 +19,21 @@ pub fn cell_to_char(cell:MinesweeperCell) -> char{
         MinesweeperCell::Number(11) => 'B',
         MinesweeperCell::Number(12) => 'C',
         MinesweeperCell::Number(val) => from_digit(val.try_into().unwrap(), 36).unwrap(),
+        MinesweeperCell::Wall(_) => 'W',
         MinesweeperCell::Empty => ' ',
         MinesweeperCell::Question => '?',
         MinesweeperCell::Mine => 'F',
     } 
 }
 
+#[derive(Copy)]
+#[derive(Clone)]
+#[derive(PartialEq, Eq)]
 pub enum NextToPolicy {
     EightAround,
     XScape,
-    XSmall
+    XSmall,
+    Knight
 }
 
 pub trait BoardIndexable {
@@ -39,51 +47,61 @@ pub trait BoardIndexable {
 
     fn get_next_to_x_small(&self, index: usize) -> Vec<usize>;
 
-    fn empty_and_mine_count(&self, next_to:&Vec<usize>) -> (Vec<usize>, usize);
+    fn get_next_to_knight(&self, index: usize) -> Vec<usize>;
+
+    fn get_ring_around(&self, index: usize) -> Vec<Option<usize>>;
 
-    fn black_white_m_minecount(&self, next_to:&Vec<usize>) -> (Vec<usize>, Vec<usize>, usize);
+    fn get_lines_of_sight(&self, index: usize) -> Vec<Vec<usize>>;
 
-    fn black_white_split_minecount(&self, next_to:&Vec<usize>) -> (Vec<usize>, Vec<usize>, usize, usize);
+    fn empty_and_mine_count(&self, next_to:&[usize]) -> (Vec<usize>, usize);
+
+    fn black_white_m_minecount(&self, next_to:&[usize]) -> (Vec<usize>, Vec<usize>, usize);
+
+    fn black_white_split_minecount(&self, next_to:&[usize]) -> (Vec<usize>, Vec<usize>, usize, usize);
 }
 
 #[derive(Clone)]
 pub struct Board {
     pub rows : Vec<MinesweeperCell>,
-    pub size : usize
+    pub width : usize,
+    pub height : usize,
+    pub total_mines : Option<usize>,
+    pub variant : Option<Variant>
 }
 
 impl BoardIndexable for Board {
     fn as_rows(&self) -> Vec<&[MinesweeperCell]> {
-        self.rows.chunks(self.size).collect()
+        self.rows.chunks(self.width).collect()
     }
 
     fn get_next_to(&self, index: usize, policy: NextToPolicy) -> Vec<usize>{
         match policy {
             NextToPolicy::EightAround => self.get_next_to_eight_around(index),
             NextToPolicy::XScape => self.get_next_to_x(index),
-            NextToPolicy::XSmall => self.get_next_to_x_small(index)
+            NextToPolicy::XSmall => self.get_next_to_x_small(index),
+            NextToPolicy::Knight => self.get_next_to_knight(index)
         }
     }
 
     fn get_next_to_eight_around(&self, index: usize) -> Vec<usize>{
-        if index >= self.size * self.size{
+        if index >= self.width * self.height{
             panic!("index {} out of range for board", index)
         }
 
         let mut res = vec![];
 
-        let up = index >= self.size;
-        let down = index < self.size * (self.size-1);
-        let left = index % self.size != 0;
-        let right = (index+1) % self.size != 0;
+        let up = index >= self.width;
+        let down = index < self.width * (self.height-1);
+        let left = index % self.width != 0;
+        let right = (index+1) % self.width != 0;
 
         if up {
             if right {
-                res.push(index - self.size + 1);
+                res.push(index - self.width + 1);
             }
-            res.push(index - self.size);
+            res.push(index - self.width);
             if left {
-                res.push(index - self.size - 1);
+                res.push(index - self.width - 1);
             }
         }
         if right {
@@ -94,11 +112,11 @@ impl BoardIndexable for Board {
         }
         if down {
             if right {
-                res.push(index + self.size + 1);
+                res.push(index + self.width + 1);
             }
-            res.push(index + self.size);
+            res.push(index + self.width);
             if left {
-                res.push(index + self.size - 1);
+                res.push(index + self.width - 1);
             }
         }
 
@@ -106,28 +124,28 @@ impl BoardIndexable for Board {
     }
 
     fn get_next_to_x(&self, index: usize) -> Vec<usize>{
-        if index >= self.size * self.size{
+        if index >= self.width * self.height{
             panic!("index {} out of range for board", index)
         }
 
         let mut res = vec![];
 
-        let up = index >= self.size;
-        let down = index < self.size * (self.size-1);
-        let left = index % self.size != 0;
-        let right = (index+1) % self.size != 0;
+        let up = index >= self.width;
+        let down = index < self.width * (self.height-1);
+        let left = index % self.width != 0;
+        let right = (index+1) % self.width != 0;
 
         if up {
-            res.push(index-self.size);
+            res.push(index-self.width);
 
-            if index-self.size >= self.size {
-                res.push(index-self.size-self.size);
+            if index-self.width >= self.width {
+                res.push(index-self.width-self.width);
             }
         }
         if right {
             res.push(index+1);
 
-            if (index+2) % self.size != 0 {
+            if (index+2) % self.width != 0 {
                 
                 res.push(index+2);
             }
@@ -135,15 +153,15 @@ impl BoardIndexable for Board {
         if left {
             res.push(index-1);
 
-            if (index-1) % self.size != 0 {
+            if (index-1) % self.width != 0 {
                 res.push(index-2);
             }
         }
         if down {
-            res.push(index+self.size);
+            res.push(index+self.width);
 
-            if index+self.size < self.size * (self.size-1) {
-                res.push(index+self.size+self.size);
+            if index+self.width < self.width * (self.height-1) {
+                res.push(index+self.width+self.width);
             }
         }
 
@@ -151,19 +169,19 @@ impl BoardIndexable for Board {
     }
 
     fn get_next_to_x_small(&self, index: usize) -> Vec<usize> {
-        if index >= self.size * self.size{
+        if index >= self.width * self.height{
             panic!("index {} out of range for board", index)
         }
 
         let mut res = vec![];
 
-        let up = index >= self.size;
-        let down = index < self.size * (self.size-1);
-        let left = index % self.size != 0;
-        let right = (index+1) % self.size != 0;
+        let up = index >= self.width;
+        let down = index < self.width * (self.height-1);
+        let left = index % self.width != 0;
+        let right = (index+1) % self.width != 0;
 
         if up {
-            res.push(index - self.size);
+            res.push(index - self.width);
         }
         if right {
             res.push(index+1);
@@ -172,14 +190,71 @@ impl BoardIndexable for Board {
             res.push(index-1);
         }
         if down {
-            res.push(index + self.size);
+            res.push(index + self.width);
         }
 
         res
     }
 
-    fn empty_and_mine_count(&self, next_to:&Vec<usize>) -> (Vec<usize>, usize) {
-        next_to.to_owned().into_iter().fold((Vec::new(),0), |(mut empty, mines), i| {
+    fn get_next_to_knight(&self, index: usize) -> Vec<usize> {
+        if index >= self.width * self.height{
+            panic!("index {} out of range for board", index)
+        }
+
+        let x = (index % self.width) as isize;
+        let y = (index / self.width) as isize;
+        let width = self.width as isize;
+        let height = self.height as isize;
+
+        [(1, -2), (2, -1), (2, 1), (1, 2), (-1, 2), (-2, 1), (-2, -1), (-1, -2)].into_iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .filter(|(nx, ny)| *nx >= 0 && *nx < width && *ny >= 0 && *ny < height)
+            .map(|(nx, ny)| (ny * width + nx) as usize)
+            .collect()
+    }
+
+    // The eight cells around index in clockwise order starting at the top left, None when off the board
+    fn get_ring_around(&self, index: usize) -> Vec<Option<usize>> {
+        if index >= self.width * self.height{
+            panic!("index {} out of range for board", index)
+        }
+
+        let up = index >= self.width;
+        let down = index < self.width * (self.height-1);
+        let left = index % self.width != 0;
+        let right = (index+1) % self.width != 0;
+
+        vec![
+            if up && left {Some(index - self.width - 1)} else {None},
+            if up {Some(index - self.width)} else {None},
+            if up && right {Some(index - self.width + 1)} else {None},
+            if right {Some(index + 1)} else {None},
+            if down && right {Some(index + self.width + 1)} else {None},
+            if down {Some(index + self.width)} else {None},
+            if down && left {Some(index + self.width - 1)} else {None},
+            if left {Some(index - 1)} else {None},
+        ]
+    }
+
+    // The cells seen looking up, right, down and left from index, nearest first, up to the edge of the board
+    fn get_lines_of_sight(&self, index: usize) -> Vec<Vec<usize>> {
+        if index >= self.width * self.height{
+            panic!("index {} out of range for board", index)
+        }
+
+        let x = index % self.width;
+        let y = index / self.width;
+
+        vec![
+            (0..y).rev().map(|row| row * self.width + x).collect(),
+            (x+1..self.width).map(|column| y * self.width + column).collect(),
+            (y+1..self.height).map(|row| row * self.width + x).collect(),
+            (0..x).rev().map(|column| y * self.width + column).collect(),
+        ]
+    }
+
+    fn empty_and_mine_count(&self, next_to:&[usize]) -> (Vec<usize>, usize) {
+        next_to.iter().copied().fold((Vec::new(),0), |(mut empty, mines), i| {
             match self[i] {
                 MinesweeperCell::Empty => {empty.push(i); (empty, mines)},
                 MinesweeperCell::Mine => (empty, mines + 1),
@@ -188,36 +263,36 @@ impl BoardIndexable for Board {
         })
     }
 
-    fn black_white_m_minecount(&self, next_to:&Vec<usize>) -> (Vec<usize>, Vec<usize>, usize) {
-        next_to.to_owned().into_iter().fold((Vec::new(),Vec::new(),0), |(mut black, mut white, mines), i| {
+    fn black_white_m_minecount(&self, next_to:&[usize]) -> (Vec<usize>, Vec<usize>, usize) {
+        next_to.iter().copied().fold((Vec::new(),Vec::new(),0), |(mut black, mut white, mines), i| {
             match self[i] {
-                MinesweeperCell::Empty => {if is_square_id_black(i, self.size) {black.push(i)} else {white.push(i)}; (black, white, mines)},
-                MinesweeperCell::Mine => (black, white, mines + if is_square_id_black(i, self.size) {1} else {2}),
+                MinesweeperCell::Empty => {if is_square_id_black(i, self.width) {black.push(i)} else {white.push(i)}; (black, white, mines)},
+                MinesweeperCell::Mine => (black, white, mines + if is_square_id_black(i, self.width) {1} else {2}),
                 _ => (black, white, mines)
             }
         })
     }
 
-    fn black_white_split_minecount(&self, next_to:&Vec<usize>) -> (Vec<usize>, Vec<usize>, usize, usize) {
-        next_to.to_owned().into_iter().fold((Vec::new(),Vec::new(),0,0), |(mut black, mut white, black_mines, white_mines), i| {
+    fn black_white_split_minecount(&self, next_to:&[usize]) -> (Vec<usize>, Vec<usize>, usize, usize) {
+        next_to.iter().copied().fold((Vec::new(),Vec::new(),0,0), |(mut black, mut white, black_mines, white_mines), i| {
             match self[i] {
-                MinesweeperCell::Empty => {if is_square_id_black(i, self.size) {black.push(i)} else {white.push(i)}; (black, white, black_mines, white_mines)},
-                MinesweeperCell::Mine => (black, white, black_mines + if is_square_id_black(i, self.size) {1} else {0}, white_mines + if is_square_id_black(i, self.size) {0} else {1}, ),
+                MinesweeperCell::Empty => {if is_square_id_black(i, self.width) {black.push(i)} else {white.push(i)}; (black, white, black_mines, white_mines)},
+                MinesweeperCell::Mine => (black, white, black_mines + if is_square_id_black(i, self.width) {1} else {0}, white_mines + if is_square_id_black(i, self.width) {0} else {1}, ),
                 _ => (black, white, black_mines, white_mines)
             }
         })
     }
 }
 
-fn is_square_id_black(id:usize, size:usize) -> bool{
-    if size % 2 == 1 { return id % 2 == 0}
+fn is_square_id_black(id:usize, width:usize) -> bool{
+    if width % 2 == 1 { return id % 2 == 0}
 
     let mut y = 0;
     let mut x = id;
 
-    while x >= size {
+    while x >= width {
         y += 1;
-        x -= size;
+        x -= width;
     }
 
     x % 2 == y % 2
@@ -236,6 +311,8 @@ impl fmt::Display for MinesweeperCell {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self{
             MinesweeperCell::Number(val) => write!(f, "{}", val),
+            MinesweeperCell::Wall([0, 0, 0, 0]) => write!(f, "0"),
+            MinesweeperCell::Wall(runs) => write!(f, "{}", runs.iter().filter(|r| **r > 0).map(|r| r.to_string()).collect::<Vec<String>>().join(" ")),
             MinesweeperCell::Empty => write!(f, "Empty"),
             MinesweeperCell::Question => write!(f, "?"),
             MinesweeperCell::Mine => write!(f, "Flag"),
@@ -243,17 +320,125 @@ impl fmt::Display for MinesweeperCell {
     }
 }
 
+// A board as text is a header with the variant code, the size and the total number of mines, followed by one line per row.
+// Unknown variants and mine counts are written as ?. The grid shows walls as W, so the runs of each wall follow the grid
+// on their own line in reading order, written the way a wall cell displays:
+//   QW 5x3 6
+//   1W? F
+//   2 W
+//    ?  3
+//   W 1 2
+//   W 0
 impl fmt::Display for Board {
     
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let variant = self.variant.map_or("?".to_string(), |variant| variant.to_string());
+        let total_mines = self.total_mines.map_or("?".to_string(), |total| total.to_string());
+        writeln!(f, "{} {}x{} {}", variant, self.width, self.height, total_mines)?;
+
         for row in self.as_rows(){
-            let row_text: String = row.into_iter().map(|c| cell_to_char(*c)).collect();
-            write!(f, "{}\n", row_text).unwrap();
+            let row_text: String = row.iter().map(|c| cell_to_char(*c)).collect();
+            writeln!(f, "{}", row_text)?;
         };
+        for cell in &self.rows{
+            if let MinesweeperCell::Wall(_) = cell {
+                writeln!(f, "W {}", cell)?;
+            }
+        }
         write!(f, "")
     }
 }
 
+fn char_to_cell(c:char) -> Option<MinesweeperCell>{
+    match c {
+        'A' => Some(MinesweeperCell::Number(10)),
+        'B' => Some(MinesweeperCell::Number(11)),
+        'C' => Some(MinesweeperCell::Number(12)),
+        'W' => Some(MinesweeperCell::Wall([0; 4])),
+        ' ' => Some(MinesweeperCell::Empty),
+        '?' => Some(MinesweeperCell::Question),
+        'F' => Some(MinesweeperCell::Mine),
+        _ => c.to_digit(36).filter(|_| !c.is_ascii_uppercase()).map(|val| MinesweeperCell::Number(val as usize))
+    }
+}
+
+impl FromStr for Board {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Board, String> {
+        let mut lines = text.lines();
+
+        let header = lines.next().ok_or("Board text is empty")?;
+        let invalid_header = || format!("Invalid board header: {}", header);
+        let words: Vec<&str> = header.split_whitespace().collect();
+        if words.len() != 3 {
+            return Err(invalid_header());
+        }
+
+        let variant = match words[0] {
+            "?" => None,
+            code => Some(Variant::from_code(code).ok_or_else(invalid_header)?)
+        };
+        let (width, height) = words[1].split_once('x').ok_or_else(invalid_header)?;
+        let width: usize = width.parse().map_err(|_| invalid_header())?;
+        let height: usize = height.parse().map_err(|_| invalid_header())?;
+        // A board without squares cannot be laid out in rows
+        if width == 0 || height == 0 {
+            return Err(invalid_header());
+        }
+        let total_mines = match words[2] {
+            "?" => None,
+            total => Some(total.parse().map_err(|_| invalid_header())?)
+        };
+
+        let mut rows = Vec::with_capacity(width*height);
+        for y in 0..height {
+            // Editors strip trailing spaces, so short rows are filled up with empty cells
+            let line = lines.next().ok_or(format!("Board text ends at row {} of {}", y, height))?;
+            if line.chars().count() > width {
+                return Err(format!("Row {} is wider than {}: {}", y, width, line));
+            }
+
+            for c in line.chars().chain(std::iter::repeat(' ')).take(width) {
+                rows.push(char_to_cell(c).ok_or(format!("Unknown cell {:?} in row {}", c, y))?);
+            }
+        }
+
+        for cell in rows.iter_mut().filter(|cell| matches!(cell, MinesweeperCell::Wall(_))) {
+            let line = lines.next().ok_or("Board text is missing the runs of a wall")?;
+            let invalid_wall = || format!("Invalid wall: {}", line);
+
+            let mut runs = line.strip_prefix("W ").ok_or_else(invalid_wall)?.split_whitespace()
+                .map(|run| run.parse::<usize>().map_err(|_| invalid_wall()))
+                .collect::<Result<Vec<usize>, String>>()?;
+            runs.retain(|run| *run > 0);
+            if runs.len() > 4 {
+                return Err(invalid_wall());
+            }
+            runs.sort_by(|a, b| b.cmp(a));
+            runs.resize(4, 0);
+
+            *cell = MinesweeperCell::Wall(runs.try_into().unwrap());
+        }
+
+        if let Some(line) = lines.find(|line| !line.trim().is_empty()) {
+            return Err(format!("Unexpected text after the board: {}", line));
+        }
+
+        Ok(Board {rows, width, height, total_mines, variant})
+    }
+}
+
+pub fn cells_left(board:&Board) -> usize{
+    let (empties, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
+
+    empties.len()
+}
+
+pub fn empty_board(width:usize, height:usize) -> Board{
+    Board {rows:vec![MinesweeperCell::Empty; width*height], width, height, total_mines:None, variant:None}
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -261,7 +446,7 @@ mod tests {
 
     #[test]
     fn correct_next_to() {
-        let board = Board {rows:vec![MinesweeperCell::Empty; 64], size:8};
+        let board = Board {rows:vec![MinesweeperCell::Empty; 64], width:8, height:8, total_mines:None, variant:None};
         for i in 0..64{
             assert_eq!(board.get_next_to(i, NextToPolicy::EightAround).len(), {
                 match i {
@@ -275,7 +460,7 @@ mod tests {
 
     #[test]
     fn correct_next_to_x() {
-        let board = Board {rows:vec![MinesweeperCell::Empty; 25], size:5};
+        let board = Board {rows:vec![MinesweeperCell::Empty; 25], width:5, height:5, total_mines:None, variant:None};
         for i in 0..25{
             println!("{}", i);
             assert_eq!(board.get_next_to(i, NextToPolicy::XScape).len(), {
@@ -291,10 +476,67 @@ mod tests {
             }, "testing: {} val {:?}", i, board.get_next_to(i, NextToPolicy::XScape))
         }
     }
-}
 
-pub fn cells_left(board:&Board) -> usize{
-    let (empties, _) = board.empty_and_mine_count(&(0..board.size*board.size).collect());
+    #[test]
+    fn correct_next_to_knight() {
+        let board = Board {rows:vec![MinesweeperCell::Empty; 25], width:5, height:5, total_mines:None, variant:None};
+        for i in 0..25{
+            assert_eq!(board.get_next_to(i, NextToPolicy::Knight).len(), {
+                match i {
+                    0|4|20|24 => 2,
+                    1|3|5|9|15|19|21|23 => 3,
+                    2|10|14|22 => 4,
+                    6|8|16|18 => 4,
+                    7|11|13|17 => 6,
+                    12 => 8,
+                    _ => panic!("die")
+                }
+            }, "testing: {} val {:?}", i, board.get_next_to(i, NextToPolicy::Knight))
+        }
+    }
 
-    empties.len()
-}
\ No newline at end of file
+    #[test]
+    fn correct_next_to_rectangular() {
+        let board = empty_board(5, 3);
+        assert_eq!(board.get_next_to(0, NextToPolicy::EightAround).len(), 3);
+        assert_eq!(board.get_next_to(7, NextToPolicy::EightAround).len(), 8);
+        assert_eq!(board.get_next_to(14, NextToPolicy::EightAround).len(), 3);
+        assert_eq!(board.get_next_to(7, NextToPolicy::XScape).len(), 6);
+        assert_eq!(board.get_next_to(2, NextToPolicy::Knight), vec![9, 13, 11, 5]);
+        assert_eq!(board.get_lines_of_sight(7), vec![vec![2], vec![8, 9], vec![12], vec![6, 5]]);
+        assert_eq!(board.as_rows().len(), 3);
+    }
+
+    #[test]
+    fn correct_ring_around() {
+        let board = Board {rows:vec![MinesweeperCell::Empty; 25], width:5, height:5, total_mines:None, variant:None};
+        assert_eq!(board.get_ring_around(12), vec![Some(6), Some(7), Some(8), Some(13), Some(18), Some(17), Some(16), Some(11)]);
+        assert_eq!(board.get_ring_around(0), vec![None, None, None, Some(1), Some(6), Some(5), None, None]);
+        assert_eq!(board.get_ring_around(19), vec![Some(13), Some(14), None, None, None, Some(24), Some(23), Some(18)]);
+    }
+
+    #[test]
+    fn correct_board_text() {
+        let text = "QW 5x3 6\n1W? F\n2 W\n ?  3\nW 1 2\nW 0\n";
+        let board: Board = text.parse().unwrap();
+
+        assert_eq!((board.width, board.height, board.total_mines), (5, 3, Some(6)));
+        assert!(board.variant == Variant::from_code("QW"));
+        assert!(board[1] == MinesweeperCell::Wall([2, 1, 0, 0]));
+        assert!(board[7] == MinesweeperCell::Wall([0; 4]));
+        assert!(board[4] == MinesweeperCell::Mine);
+        assert!(board[14] == MinesweeperCell::Number(3));
+        assert_eq!(board.to_string(), "QW 5x3 6\n1W? F\n2 W  \n ?  3\nW 2 1\nW 0\n");
+
+        let unknown = Board {rows:vec![MinesweeperCell::Number(11), MinesweeperCell::Empty], width:2, height:1, total_mines:None, variant:None};
+        let reparsed: Board = unknown.to_string().parse().unwrap();
+        assert_eq!(reparsed.to_string(), "? 2x1 ?\nB \n");
+
+        assert!("V 2x2 1\n12\n".parse::<Board>().is_err());
+        assert!("V 2x1 1\n1Z\n".parse::<Board>().is_err());
+        assert!("V 2x1 1\nW1\n".parse::<Board>().is_err());
+        assert!("V 2 1\n12\n".parse::<Board>().is_err());
+        assert!("V 0x3 ?\n".parse::<Board>().is_err());
+        assert!("V 3x0 ?\n".parse::<Board>().is_err());
+    }
+}
@@ -1,48 +1,69 @@
 use image::{RgbaImage, GenericImageView};
 use image::imageops::{resize, Gaussian};
+#[cfg(windows)]
 use win_screenshot::prelude::*;
 
-const EIGHT_TOP_LEFT: (u32,u32) = (606,271);
-const SEVEN_TOP_LEFT: (u32, u32) = (652,316);
-const SQUARE_SIZE: (u32,u32) = (92,92);
-const SQUARE_IMAGE_CROP_SIZE: (u32,u32) = (83,83);
+use crate::calibration::Calibration;
+use crate::error::SolverError;
+
+// The part of a square that is cropped, as 83 pixels out of a pitch of 92
+const SQUARE_IMAGE_CROP: (i32,i32) = (83,92);
 const DOWNSCALE_SIZE: (u32,u32) = (10,10);
+pub const COUNTER_DIGITS: u32 = 2;
 
 
-pub fn get_whole_window() -> RgbaImage{
+#[cfg(windows)]
+pub fn get_whole_window() -> Result<RgbaImage, SolverError>{
     // Capture window if you know the exact name
-    let hwnd = find_window("Minesweeper Variants").unwrap();
-    let buf = capture_window(hwnd).unwrap();
+    let hwnd = find_window("Minesweeper Variants").map_err(|_| SolverError::Capture("the game window is not open".to_string()))?;
+    let buf = capture_window(hwnd).map_err(|err| SolverError::Capture(format!("{:?}", err)))?;
 
     // convert to image and save
-    RgbaImage::from_raw(buf.width, buf.height, buf.pixels).unwrap()
+    RgbaImage::from_raw(buf.width, buf.height, buf.pixels).ok_or(SolverError::Capture("the captured image has the wrong size".to_string()))
 }
 
+#[cfg(not(windows))]
+pub fn get_whole_window() -> Result<RgbaImage, SolverError>{
+    Err(SolverError::Capture("capturing the game window needs Windows, solve a saved screenshot with the image command instead".to_string()))
+}
 
-pub fn save_image(img:RgbaImage){
-    img.save("images/screenshot.jpg").unwrap();
+pub fn load_screenshot(path:&str) -> Result<RgbaImage, SolverError>{
+    image::open(path).map(|img| img.into_rgba8()).map_err(|err| SolverError::Capture(format!("{}: {}", path, err)))
 }
 
+// A board whose squares fall outside the screenshot, or that has no squares at all, is an unsupported size
+pub fn get_square_image(full_screenshot:&RgbaImage, calibration:&Calibration, width:usize, height:usize, square_x:u32, square_y:u32) -> Result<RgbaImage, SolverError> {
+    let unsupported = SolverError::UnsupportedSize { width, height };
+    if width == 0 || height == 0 {
+        return Err(unsupported);
+    }
+
+    let grid = calibration.grid(width, height);
 
-pub fn get_square_image(full_screenshot:&RgbaImage, board_size:usize, square_x:u32, square_y:u32) -> RgbaImage {
-    let x = match board_size {
-        8 => EIGHT_TOP_LEFT.0,
-        7 => SEVEN_TOP_LEFT.0,
-        6 => EIGHT_TOP_LEFT.0 + SQUARE_SIZE.0,
-        5 => SEVEN_TOP_LEFT.0 + SQUARE_SIZE.0,
-        _ => panic!("Board size is not 5-8")
-    } + square_x * SQUARE_SIZE.0;
+    let (x, y) = grid.square_origin(square_x.try_into().unwrap(), square_y.try_into().unwrap());
+    let crop = (grid.pitch.0 * SQUARE_IMAGE_CROP.0 / SQUARE_IMAGE_CROP.1, grid.pitch.1 * SQUARE_IMAGE_CROP.0 / SQUARE_IMAGE_CROP.1);
+
+    let inside = x >= 0 && y >= 0 && crop.0 > 0 && crop.1 > 0
+        && ((x + crop.0) as u32) <= full_screenshot.width() && ((y + crop.1) as u32) <= full_screenshot.height();
+    if !inside {
+        return Err(unsupported);
+    }
+
+    Ok(downscale_image(full_screenshot.view(x as u32, y as u32, crop.0 as u32, crop.1 as u32).to_image()))
+}
 
-    let y = match board_size {
-        8 => EIGHT_TOP_LEFT.1,
-        7 => SEVEN_TOP_LEFT.1,
-        6 => EIGHT_TOP_LEFT.1 + SQUARE_SIZE.1,
-        5 => SEVEN_TOP_LEFT.1 + SQUARE_SIZE.1,
-        _ => panic!("Board size is not 5-8")
-    } + square_y * SQUARE_SIZE.1;
+// A digit of the mine counter, or None when the counter falls outside the screenshot
+pub fn get_counter_digit_image(full_screenshot:&RgbaImage, calibration:&Calibration, digit:u32) -> Option<RgbaImage> {
+    let (x, y) = calibration.counter_digit_origin(digit);
+    let size = calibration.counter_digit;
 
+    let inside = x >= 0 && y >= 0 && size.0 > 0 && size.1 > 0
+        && ((x + size.0) as u32) <= full_screenshot.width() && ((y + size.1) as u32) <= full_screenshot.height();
+    if !inside {
+        return None;
+    }
 
-    downscale_image(full_screenshot.view(x,y,SQUARE_IMAGE_CROP_SIZE.0,SQUARE_IMAGE_CROP_SIZE.1).to_image())
+    Some(downscale_image(full_screenshot.view(x as u32, y as u32, size.0 as u32, size.1 as u32).to_image()))
 }
 
 fn downscale_image(img: RgbaImage) -> RgbaImage{
@@ -0,0 +1,303 @@
+use image::RgbaImage;
+use std::fs;
+
+use crate::screen_grid::ScreenGrid;
+
+// Where the board is in the captured window and how the window maps onto the screen.
+// Cropping squares works in window pixels and clicking in screen pixels, so both read their positions from here.
+// A calibration is found by detecting the grid lines of a screenshot and can be saved to a text file:
+//   center 974 639
+//   max_pitch 92 92
+//   max_extent 736 736
+//   screen_offset 16 6
+//   screen_scale 0.9565
+//   counter_offset 28 -471
+//   counter_digit 26 40
+// The mine counter sits at a fixed offset from the centre of the board, given as the top left of its first digit,
+// and a file without it takes the offset and digit size of the standard calibration scaled to its board extent.
+
+// Boards up to this many squares across are drawn with squares of full size
+const FULL_PITCH_SQUARES: i32 = 8;
+// Grid lines are never closer together than this
+const MIN_PITCH: i32 = 16;
+// How far a detected grid line can be from where the pitch puts it
+const LINE_TOLERANCE: i32 = 2;
+// A board has at least this many grid lines in each direction
+const MIN_LINES: usize = 4;
+
+#[derive(Clone)]
+#[derive(Debug, PartialEq)]
+pub struct Calibration {
+    pub center: (i32,i32),
+    pub max_pitch: (i32,i32),
+    pub max_extent: (i32,i32),
+    pub screen_offset: (i32,i32),
+    pub screen_scale: f64,
+    pub counter_offset: (i32,i32),
+    pub counter_digit: (i32,i32),
+}
+
+impl Calibration {
+    // The monitor the solver was first tuned on
+    pub fn standard() -> Calibration {
+        Calibration {
+            center: (974, 639),
+            max_pitch: (92, 92),
+            max_extent: (736, 736),
+            screen_offset: (16, 6),
+            screen_scale: 88.0 / 92.0,
+            counter_offset: (28, -471),
+            counter_digit: (26, 40),
+        }
+    }
+
+    // Keeps the mapping to the screen of this calibration and takes the board geometry from the screenshot.
+    // Returns the calibration together with the width and height of the board that was found.
+    pub fn detect(&self, img:&RgbaImage) -> Option<(Calibration, usize, usize)> {
+        let (grid, width, height) = detect_grid(img)?;
+
+        let squares = i32::try_from(width.max(height)).unwrap();
+        let w = i32::try_from(width).unwrap();
+        let h = i32::try_from(height).unwrap();
+
+        let max_extent = (grid.pitch.0 * squares.max(FULL_PITCH_SQUARES), grid.pitch.1 * squares.max(FULL_PITCH_SQUARES));
+
+        let calibration = Calibration {
+            center: (grid.top_left.0 + w * grid.pitch.0 / 2, grid.top_left.1 + h * grid.pitch.1 / 2),
+            max_pitch: (max_extent.0 / FULL_PITCH_SQUARES, max_extent.1 / FULL_PITCH_SQUARES),
+            max_extent,
+            screen_offset: self.screen_offset,
+            screen_scale: self.screen_scale,
+            counter_offset: scale_to_extent(self.counter_offset, self.max_extent, max_extent),
+            counter_digit: scale_to_extent(self.counter_digit, self.max_extent, max_extent),
+        };
+
+        Some((calibration, width, height))
+    }
+
+    pub fn grid(&self, width:usize, height:usize) -> ScreenGrid {
+        ScreenGrid::centered(self.center, self.max_pitch, self.max_extent, width, height)
+    }
+
+    // Top left of a digit of the mine counter, counting digits from the left
+    pub fn counter_digit_origin(&self, digit:u32) -> (i32,i32) {
+        (self.center.0 + self.counter_offset.0 + i32::try_from(digit).unwrap() * self.counter_digit.0,
+         self.center.1 + self.counter_offset.1)
+    }
+
+    pub fn to_screen(&self, window_point:(i32,i32)) -> (i32,i32) {
+        (self.screen_offset.0 + (f64::from(window_point.0) * self.screen_scale).round() as i32,
+         self.screen_offset.1 + (f64::from(window_point.1) * self.screen_scale).round() as i32)
+    }
+
+    pub fn to_text(&self) -> String {
+        format!("center {} {}\nmax_pitch {} {}\nmax_extent {} {}\nscreen_offset {} {}\nscreen_scale {}\ncounter_offset {} {}\ncounter_digit {} {}\n",
+            self.center.0, self.center.1,
+            self.max_pitch.0, self.max_pitch.1,
+            self.max_extent.0, self.max_extent.1,
+            self.screen_offset.0, self.screen_offset.1,
+            self.screen_scale,
+            self.counter_offset.0, self.counter_offset.1,
+            self.counter_digit.0, self.counter_digit.1)
+    }
+
+    pub fn from_text(text:&str) -> Result<Calibration, String> {
+        let mut calibration = Calibration::standard();
+        let mut has_counter = false;
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || format!("Invalid calibration on line {}: {}", number + 1, line);
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let pair = || -> Result<(i32,i32), String> {
+                if words.len() != 3 {
+                    return Err(invalid());
+                }
+                Ok((words[1].parse().map_err(|_| invalid())?, words[2].parse().map_err(|_| invalid())?))
+            };
+
+            match words[0] {
+                "center" => calibration.center = pair()?,
+                "max_pitch" => calibration.max_pitch = pair()?,
+                "max_extent" => calibration.max_extent = pair()?,
+                "screen_offset" => calibration.screen_offset = pair()?,
+                "counter_offset" => {
+                    calibration.counter_offset = pair()?;
+                    has_counter = true;
+                },
+                "counter_digit" => {
+                    calibration.counter_digit = pair()?;
+                    has_counter = true;
+                },
+                "screen_scale" => {
+                    if words.len() != 2 {
+                        return Err(invalid());
+                    }
+                    calibration.screen_scale = words[1].parse().map_err(|_| invalid())?;
+                },
+                _ => return Err(invalid())
+            }
+        }
+
+        if !has_counter {
+            let standard = Calibration::standard();
+            calibration.counter_offset = scale_to_extent(standard.counter_offset, standard.max_extent, calibration.max_extent);
+            calibration.counter_digit = scale_to_extent(standard.counter_digit, standard.max_extent, calibration.max_extent);
+        }
+
+        Ok(calibration)
+    }
+
+    pub fn load(path:&str) -> Result<Calibration, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("Unable to read {}: {}", path, err))?;
+        Calibration::from_text(&text)
+    }
+
+    pub fn save(&self, path:&str) -> Result<(), String> {
+        fs::write(path, self.to_text()).map_err(|err| format!("Unable to write {}: {}", path, err))
+    }
+}
+
+// The window is drawn bigger or smaller as a whole, so lengths outside the board scale with the extent of the board
+fn scale_to_extent(length:(i32,i32), from_extent:(i32,i32), to_extent:(i32,i32)) -> (i32,i32) {
+    (length.0 * to_extent.0 / from_extent.0, length.1 * to_extent.1 / from_extent.1)
+}
+
+// Finds the grid in a screenshot from the columns and rows where the brightness changes the most.
+// Grid lines show up as evenly spaced peaks, so the longest evenly spaced run of peaks in each direction is the board.
+pub fn detect_grid(img:&RgbaImage) -> Option<(ScreenGrid, usize, usize)> {
+    let (left, pitch_x, lines_x) = find_evenly_spaced(&find_peaks(&edge_profile(img, true)))?;
+    let (top, pitch_y, lines_y) = find_evenly_spaced(&find_peaks(&edge_profile(img, false)))?;
+
+    Some((ScreenGrid { top_left: (left, top), pitch: (pitch_x, pitch_y) }, lines_x - 1, lines_y - 1))
+}
+
+fn brightness(img:&RgbaImage, x:u32, y:u32) -> i64 {
+    let pixel = img.get_pixel(x, y);
+    i64::from(pixel[0]) + i64::from(pixel[1]) + i64::from(pixel[2])
+}
+
+// How much the brightness changes between each column and the next, or between each row and the next
+fn edge_profile(img:&RgbaImage, columns:bool) -> Vec<i64> {
+    let (length, across) = if columns {(img.width(), img.height())} else {(img.height(), img.width())};
+
+    (0..length.saturating_sub(1)).map(|i| (0..across).map(|j| {
+        if columns {
+            (brightness(img, i + 1, j) - brightness(img, i, j)).abs()
+        } else {
+            (brightness(img, j, i + 1) - brightness(img, j, i)).abs()
+        }
+    }).sum()).collect()
+}
+
+// Positions that are the strongest change within the tolerance around them and stand out from the average.
+// Each peak comes with its strength.
+fn find_peaks(profile:&[i64]) -> Vec<(i32,i64)> {
+    if profile.is_empty() {
+        return vec![];
+    }
+
+    let mean = profile.iter().sum::<i64>() / i64::try_from(profile.len()).unwrap();
+    let spread = LINE_TOLERANCE as usize * 2;
+
+    profile.iter().enumerate().filter(|(i, value)| {
+        let from = i.saturating_sub(spread);
+        let to = (i + spread + 1).min(profile.len());
+        **value > mean * 2 && (from..to).all(|j| profile[j] < **value || (profile[j] == **value && j >= *i))
+    }).map(|(i, value)| (i32::try_from(i).unwrap() + 1, *value)).collect()
+}
+
+// The longest run of peaks with the same spacing, as the first line, the spacing and the number of lines.
+// Between runs of the same length the one made of the stronger lines wins.
+fn find_evenly_spaced(peaks:&[(i32,i64)]) -> Option<(i32, i32, usize)> {
+    let mut best: Option<(usize, i64, i32, i32)> = None;
+
+    for (i, first) in peaks.iter().enumerate() {
+        for second in peaks[i+1..].iter() {
+            let pitch = second.0 - first.0;
+            if pitch < MIN_PITCH {
+                continue;
+            }
+
+            let mut lines = vec![*first, *second];
+            while let Some(next) = peaks.iter().find(|peak| (peak.0 - lines.last().unwrap().0 - pitch).abs() <= LINE_TOLERANCE) {
+                lines.push(*next);
+            }
+
+            let count = lines.len();
+            let strength = lines.iter().map(|line| line.1).sum::<i64>();
+            let start = lines[0].0;
+            let pitch = (lines[count - 1].0 - start + i32::try_from(count - 1).unwrap() / 2) / i32::try_from(count - 1).unwrap();
+
+            if best.is_none_or(|(best_count, best_strength, _, _)| count > best_count || (count == best_count && strength > best_strength)) {
+                best = Some((count, strength, start, pitch));
+            }
+        }
+    }
+
+    best.filter(|(count, _, _, _)| *count >= MIN_LINES).map(|(count, _, start, pitch)| (start, pitch, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn draw_board(left:u32, top:u32, pitch:u32, width:u32, height:u32) -> RgbaImage {
+        let mut img = RgbaImage::from_pixel(1200, 900, Rgba([40, 40, 40, 255]));
+
+        for x in left..left + width * pitch {
+            for y in top..top + height * pitch {
+                let on_line = (x - left) % pitch < 2 || (y - top) % pitch < 2;
+                img.put_pixel(x, y, if on_line {Rgba([220, 220, 220, 255])} else {Rgba([90, 90, 90, 255])});
+            }
+        }
+        for x in left..left + width * pitch + 2 {
+            for y in top + height * pitch..top + height * pitch + 2 {
+                img.put_pixel(x, y, Rgba([220, 220, 220, 255]));
+            }
+        }
+        for y in top..top + height * pitch + 2 {
+            for x in left + width * pitch..left + width * pitch + 2 {
+                img.put_pixel(x, y, Rgba([220, 220, 220, 255]));
+            }
+        }
+
+        img
+    }
+
+    #[test]
+    fn correct_grid_detection() {
+        let (grid, width, height) = detect_grid(&draw_board(100, 80, 92, 8, 7)).unwrap();
+        assert_eq!((width, height), (8, 7));
+        assert_eq!(grid.pitch, (92, 92));
+        assert_eq!(grid.top_left, (100, 80));
+
+        let (calibration, width, height) = Calibration::standard().detect(&draw_board(200, 60, 70, 10, 10)).unwrap();
+        assert_eq!((width, height), (10, 10));
+        assert_eq!(calibration.grid(10, 10).top_left, (200, 60));
+        assert_eq!(calibration.grid(10, 10).pitch, (70, 70));
+        assert_eq!(calibration.grid(8, 8).pitch, (87, 87));
+    }
+
+    #[test]
+    fn correct_calibration_text() {
+        let calibration = Calibration::standard();
+        assert_eq!(Calibration::from_text(&calibration.to_text()), Ok(calibration.clone()));
+
+        assert_eq!(calibration.to_screen((652, 317)), (640, 309));
+        assert_eq!(calibration.grid(8, 8).top_left, (606, 271));
+
+        assert_eq!(calibration.counter_digit_origin(1), (1028, 168));
+        let halved = Calibration::from_text("max_extent 368 368\n").unwrap();
+        assert_eq!((halved.counter_offset, halved.counter_digit), ((14, -235), (13, 20)));
+
+        assert!(Calibration::from_text("center 1").is_err());
+        assert!(Calibration::from_text("zoom 2 2").is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::plus_linear_solvers::Variant;
+use crate::linear_programming_solvers::Backend;
+
+pub const USAGE: &str = "Usage: minesweeper_solver [COMMAND] [OPTIONS]
+
+Commands:
+  play              Solve puzzles in the running game (default)
+  image PATH        Solve a saved screenshot of the game window
+  text PATH         Solve a board stored in the text format
+  benchmark PATH..  Time the solver on boards stored in the text format
+  help              Show this message
+
+Options:
+  --variant CODE      Variant to solve, like V, Q, X' or QW (default V, text boards use their header)
+  --size N | WxH      Board size (default 8, images use the grid they show)
+  --mines N           Total number of mines, instead of the mine count table
+  --puzzles N         Number of puzzles to play before stopping (default 10)
+  --click-delay MS    Pause between the steps of a click (default 20)
+  --turn-delay MS     Pause after clicking before the board is read again (default 70)
+  --backend NAME      Solver to use, highs or native (default highs)
+  --dry-run           Read and solve the board without clicking
+  --calibrate         Find the board in the game window and save the calibration";
+
+#[derive(Clone)]
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Play,
+    Image(String),
+    Text(String),
+    Benchmark(Vec<String>),
+    Help,
+}
+
+#[derive(Clone)]
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub command: Command,
+    pub variant: Option<Variant>,
+    pub size: Option<(usize, usize)>,
+    pub mines: Option<usize>,
+    pub puzzles: usize,
+    pub click_delay: u64,
+    pub turn_delay: u64,
+    pub backend: Backend,
+    pub dry_run: bool,
+    pub calibrate: bool,
+}
+
+impl Options {
+    fn default(command: Command) -> Options {
+        Options {
+            command,
+            variant: None,
+            size: None,
+            mines: None,
+            puzzles: 10,
+            click_delay: 20,
+            turn_delay: 70,
+            backend: Backend::Highs,
+            dry_run: false,
+            calibrate: false,
+        }
+    }
+}
+
+fn parse_size(text:&str) -> Option<(usize, usize)> {
+    match text.split_once('x') {
+        Some((width, height)) => Some((width.parse().ok()?, height.parse().ok()?)),
+        None => text.parse().ok().map(|size| (size, size))
+    }
+}
+
+// Reads the arguments after the program name
+pub fn parse_args(args:&[String]) -> Result<Options, String> {
+    let mut args = args.iter().peekable();
+
+    let command_name = match args.peek() {
+        Some(arg) if !arg.starts_with("--") => args.next().unwrap().as_str(),
+        _ => "play"
+    };
+
+    let mut paths = Vec::new();
+    let mut options = Options::default(Command::Play);
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+
+        match arg.as_str() {
+            "--variant" => {
+                let code = value()?;
+                options.variant = Some(Variant::from_code(code).ok_or(format!("Unknown variant code {}", code))?);
+            },
+            "--size" => {
+                let size = value()?;
+                options.size = Some(parse_size(size).filter(|(width, height)| *width > 0 && *height > 0).ok_or(format!("Invalid board size {}", size))?);
+            },
+            "--mines" => options.mines = Some(value()?.parse().map_err(|_| "--mines needs a number")?),
+            "--puzzles" => options.puzzles = value()?.parse().map_err(|_| "--puzzles needs a number")?,
+            "--click-delay" => options.click_delay = value()?.parse().map_err(|_| "--click-delay needs a number of milliseconds")?,
+            "--turn-delay" => options.turn_delay = value()?.parse().map_err(|_| "--turn-delay needs a number of milliseconds")?,
+            "--backend" => {
+                let name = value()?;
+                options.backend = Backend::from_name(name).ok_or(format!("Unknown backend {}", name))?;
+            },
+            "--dry-run" => options.dry_run = true,
+            "--calibrate" => options.calibrate = true,
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag)),
+            path => paths.push(path.to_string()),
+        }
+    }
+
+    options.command = match (command_name, paths.len()) {
+        ("play", 0) => Command::Play,
+        ("help", 0) => Command::Help,
+        ("image", 1) => Command::Image(paths.remove(0)),
+        ("text", 1) => Command::Text(paths.remove(0)),
+        ("benchmark", count) if count > 0 => Command::Benchmark(paths),
+        ("play" | "help", _) => return Err(format!("{} does not take a path", command_name)),
+        ("image" | "text", _) => return Err(format!("{} needs exactly one path", command_name)),
+        ("benchmark", _) => return Err("benchmark needs at least one path".to_string()),
+        _ => return Err(format!("Unknown command {}", command_name)),
+    };
+
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args:&str) -> Result<Options, String> {
+        parse_args(&args.split_whitespace().map(|arg| arg.to_string()).collect::<Vec<String>>())
+    }
+
+    #[test]
+    fn correct_args() {
+        assert_eq!(parse(""), Ok(Options::default(Command::Play)));
+
+        let options = parse("--variant qw --size 7 --puzzles 3 --dry-run").unwrap();
+        assert_eq!(options.command, Command::Play);
+        assert_eq!(options.variant, Variant::from_code("QW"));
+        assert_eq!(options.size, Some((7, 7)));
+        assert_eq!(options.puzzles, 3);
+        assert!(options.dry_run);
+
+        let options = parse("image shot.png --size 8x6 --mines 20").unwrap();
+        assert_eq!(options.command, Command::Image("shot.png".to_string()));
+        assert_eq!(options.size, Some((8, 6)));
+        assert_eq!(options.mines, Some(20));
+        assert_eq!(options.backend, Backend::Highs);
+        assert_eq!(parse("text board.txt --backend native").unwrap().backend, Backend::Backtracking);
+
+        assert_eq!(parse("benchmark a.txt b.txt --click-delay 5").unwrap().command, Command::Benchmark(vec!["a.txt".to_string(), "b.txt".to_string()]));
+        assert_eq!(parse("text board.txt").unwrap().command, Command::Text("board.txt".to_string()));
+
+        assert!(parse("text").is_err());
+        assert!(parse("image a.png b.png").is_err());
+        assert!(parse("--variant Z").is_err());
+        assert!(parse("--size 0").is_err());
+        assert!(parse("--puzzles").is_err());
+        assert!(parse("--fast").is_err());
+        assert!(parse("--backend glpk").is_err());
+        assert!(parse("solve").is_err());
+    }
+}
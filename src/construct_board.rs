@@ -1,6 +1,9 @@
-use crate::board_image_getter::{get_whole_window, get_square_image};
-use crate::image_to_square::{parse_cell, get_img_cell_pairs, get_img_cell_pairs_m, get_img_cell_pairs_l, get_img_cell_pairs_n, get_img_cell_pairs_x_prime};
+use crate::board_image_getter::{get_whole_window, load_screenshot, get_square_image, get_counter_digit_image, COUNTER_DIGITS};
+use crate::image_to_square::{parse_cell_above, MATCH_SCORE, MIN_MATCH_SCORE, get_img_cell_pairs, get_img_cell_pairs_m, get_img_cell_pairs_l, get_img_cell_pairs_n, get_img_cell_pairs_x_prime, get_img_cell_pairs_w, get_img_cell_pairs_e, get_img_counter_pairs, parse_counter, ImgCellPair};
 use crate::board::{MinesweeperCell, Board};
+use crate::calibration::Calibration;
+use crate::error::SolverError;
+use image::RgbaImage;
 
 #[derive(Copy)]
 #[derive(Clone)]
@@ -10,18 +13,27 @@ pub enum VisionType {
     UpToNine,
     ChessSmall,
     UpToFour,
+    Wall,
+    UpToEleven,
 }
 
-pub fn get_board(board_size:usize, vision_type:VisionType) -> Board{
+pub fn get_board(calibration:&Calibration, width:usize, height:usize, vision_type:VisionType, total_mines:Option<usize>) -> Result<Board, SolverError>{
+    parse_board(&get_whole_window()?, calibration, width, height, vision_type, total_mines)
+}
 
-    let img = get_whole_window();
+// Reads a board from a saved screenshot of the game window, so puzzles can be solved without the game running
+pub fn get_board_from_image(path:&str, calibration:&Calibration, width:usize, height:usize, vision_type:VisionType, total_mines:Option<usize>) -> Result<Board, SolverError>{
+    parse_board(&load_screenshot(path)?, calibration, width, height, vision_type, total_mines)
+}
 
+fn parse_board(img:&RgbaImage, calibration:&Calibration, width:usize, height:usize, vision_type:VisionType, total_mines:Option<usize>) -> Result<Board, SolverError>{
     let mut board: Vec<MinesweeperCell> = Vec::new();
 
     let mut x=0;
     let mut y=0;
 
-    let board_size_u32 = board_size.try_into().unwrap();
+    let width_u32 = width.try_into().unwrap();
+    let height_u32 = height.try_into().unwrap();
 
     
     let img_cell_pairs = match vision_type {
@@ -29,20 +41,101 @@ pub fn get_board(board_size:usize, vision_type:VisionType) -> Board{
         VisionType::ChessBig => get_img_cell_pairs_m(),
         VisionType::UpToNine => get_img_cell_pairs_l(),
         VisionType::ChessSmall => get_img_cell_pairs_n(),
-        VisionType::UpToFour => get_img_cell_pairs_x_prime()
+        VisionType::UpToFour => get_img_cell_pairs_x_prime(),
+        VisionType::Wall => get_img_cell_pairs_w()?,
+        VisionType::UpToEleven => get_img_cell_pairs_e()
+    };
+
+    // Eyesight clues go up to 15 on an 8x8 board, but there are only templates up to 11. A larger clue would be taken
+    // for the closest of those, so for eyesight only a square that matches a template closely is recognised.
+    let min_score = match vision_type {
+        VisionType::UpToEleven => MATCH_SCORE,
+        _ => MIN_MATCH_SCORE
     };
 
-    while y < board_size_u32{
-        board.push(parse_cell(get_square_image(&img, board_size, x, y), &img_cell_pairs));
+    while y < height_u32{
+        let square = get_square_image(img, calibration, width, height, x, y)?;
+        board.push(parse_cell_above(square, &img_cell_pairs, min_score).ok_or(SolverError::UnrecognisedCell { x: x as usize, y: y as usize })?);
 
         x += 1;
 
-        if x>=board_size_u32{
+        if x>=width_u32{
             x = 0;
             y += 1;
         }
     }
 
 
-    Board {rows:board, size:board_size.into()}
-}
\ No newline at end of file
+    let placed_mines = board.iter().filter(|cell| **cell == MinesweeperCell::Mine).count();
+
+    // The counter shows the mines that are left
+    let read_total = read_mine_counter(img, calibration, &get_img_counter_pairs()).map(|left| left + placed_mines);
+    let total_mines = check_mine_counter(total_mines, read_total)?;
+
+    Ok(Board {rows:board, width, height, total_mines, variant:None})
+}
+
+fn read_mine_counter(img:&RgbaImage, calibration:&Calibration, img_counter_pairs:&Vec<ImgCellPair>) -> Option<usize>{
+    let digit_imgs = (0..COUNTER_DIGITS).map(|digit| get_counter_digit_image(img, calibration, digit)).collect::<Option<Vec<RgbaImage>>>()?;
+
+    parse_counter(digit_imgs, img_counter_pairs)
+}
+
+// The total read from the counter fills in a missing mine count. When both are known and disagree either the counter
+// was misread or the mine count is wrong, and solving with either could click a mine.
+fn check_mine_counter(expected:Option<usize>, read:Option<usize>) -> Result<Option<usize>, SolverError>{
+    match (expected, read) {
+        (Some(expected), Some(read)) if expected != read => Err(SolverError::MineCountMismatch { expected, read }),
+        (expected, read) => Ok(expected.or(read))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    // Stands in for a digit of the counter, every digit lights a different pattern of blocks
+    fn draw_digit(img:&mut RgbaImage, calibration:&Calibration, position:u32, digit:Option<usize>) {
+        let (left, top) = calibration.counter_digit_origin(position);
+        let (width, height) = calibration.counter_digit;
+
+        for x in 0..width {
+            for y in 0..height {
+                let block = ((x * 5 / width) * 7 + (y * 5 / height) * 3) as usize;
+                let lit = digit.is_some_and(|digit| (block + digit * 3) % 10 < 5);
+                img.put_pixel((left + x) as u32, (top + y) as u32, if lit {Rgba([230, 60, 60, 255])} else {Rgba([20, 20, 20, 255])});
+            }
+        }
+    }
+
+    fn draw_counter(calibration:&Calibration, digits:&[Option<usize>]) -> RgbaImage {
+        let mut img = RgbaImage::from_pixel(1200, 900, Rgba([40, 40, 40, 255]));
+        for (position, digit) in digits.iter().enumerate() {
+            draw_digit(&mut img, calibration, position as u32, *digit);
+        }
+        img
+    }
+
+    #[test]
+    fn correct_mine_counter() {
+        let calibration = Calibration::standard();
+
+        let mut templates = vec![ImgCellPair::new(get_counter_digit_image(&draw_counter(&calibration, &[None]), &calibration, 0).unwrap(), MinesweeperCell::Empty)];
+        for digit in 0..10 {
+            let img = get_counter_digit_image(&draw_counter(&calibration, &[Some(digit)]), &calibration, 0).unwrap();
+            templates.push(ImgCellPair::new(img, MinesweeperCell::Number(digit)));
+        }
+
+        for value in 10..100 {
+            assert_eq!(read_mine_counter(&draw_counter(&calibration, &[Some(value / 10), Some(value % 10)]), &calibration, &templates), Some(value));
+        }
+        assert_eq!(read_mine_counter(&draw_counter(&calibration, &[None, Some(8)]), &calibration, &templates), Some(8));
+        assert_eq!(read_mine_counter(&draw_counter(&calibration, &[Some(1), Some(0)]), &calibration, &vec![]), None);
+
+        assert_eq!(check_mine_counter(Some(26), Some(26)), Ok(Some(26)));
+        assert_eq!(check_mine_counter(None, Some(20)), Ok(Some(20)));
+        assert_eq!(check_mine_counter(Some(26), None), Ok(Some(26)));
+        assert_eq!(check_mine_counter(Some(26), Some(24)), Err(SolverError::MineCountMismatch { expected: 26, read: 24 }));
+    }
+}
@@ -0,0 +1,52 @@
+use std::fmt;
+
+// Everything that can stop a puzzle from being solved. The bot reports these and moves on or stops,
+// instead of the whole process going down on the first misread square.
+#[derive(Clone)]
+#[derive(Debug, PartialEq)]
+pub enum SolverError {
+    // The game window could not be captured, or a saved screenshot could not be read
+    Capture(String),
+    // No image of a known cell looks enough like the square at this position
+    UnrecognisedCell { x: usize, y: usize },
+    // The board does not fit in the screenshot, or has no squares at all
+    UnsupportedSize { width: usize, height: usize },
+    // No placement of mines satisfies the constraints, so the board was misread or the variant or mine count is wrong.
+    // Holds the clue cells and constraints that contradict each other, empty when they are not known.
+    Infeasible { clue_cells: Vec<(usize, usize)>, constraints: Vec<String> },
+    // The total read from the mine counter is not the total the board was expected to have
+    MineCountMismatch { expected: usize, read: usize },
+    // The solver stopped without finding a layout or showing that there is none, like on hitting a limit
+    Backend(String),
+    // Nothing on the board can be proven safe or a mine
+    Stuck,
+    // The mouse could not be moved or clicked
+    Input(String),
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolverError::Capture(reason) => write!(f, "Unable to capture the board: {}", reason),
+            SolverError::UnrecognisedCell { x, y } => write!(f, "Unable to recognise the square at {}, {}", x, y),
+            SolverError::UnsupportedSize { width, height } => write!(f, "Board size {}x{} is not supported", width, height),
+            SolverError::Infeasible { clue_cells, constraints } => {
+                write!(f, "No placement of mines fits the board")?;
+                if !clue_cells.is_empty() {
+                    let cells: Vec<String> = clue_cells.iter().map(|(x, y)| format!("{}, {}", x, y)).collect();
+                    write!(f, ", the clues at {} contradict each other", cells.join("; "))?;
+                }
+                for constraint in constraints {
+                    write!(f, "\n  {}", constraint)?;
+                }
+                Ok(())
+            },
+            SolverError::MineCountMismatch { expected, read } => write!(f, "The mine counter reads a total of {} but {} was expected, so the counter was misread or the mine count is wrong", read, expected),
+            SolverError::Backend(reason) => write!(f, "The solver could not finish: {}", reason),
+            SolverError::Stuck => write!(f, "I cannot solve this puzzle"),
+            SolverError::Input(reason) => write!(f, "Unable to click: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
@@ -1,71 +1,60 @@
 use mouse_rs::{Mouse,types::keys::Keys};
 use crate::algorithms::KnownSquares;
+use crate::calibration::Calibration;
+use crate::error::SolverError;
 use std::{thread, time};
 
-const EIGHT_TOP_LEFT: (i32,i32) = (640,309);
-const SEVEN_TOP_LEFT: (i32,i32) = (685,354);
-const SQUARE_SIZE: (i32, i32) = (88,88);
-
-fn click_square(square_x:usize, square_y:usize, board_size:usize, flag:bool){
-    if square_x >= board_size {
-        panic!("Invalid X coordinate {} on board of size {}", square_x, board_size);
-    }
-
-    if square_y >= board_size {
-        panic!("Invalid Y coordinate {} on board of size {}", square_y, board_size);
+fn click_square(square_x:usize, square_y:usize, calibration:&Calibration, width:usize, height:usize, flag:bool, click_delay:u64) -> Result<(), SolverError>{
+    // A square off the board would click somewhere else in the window
+    if square_x >= width || square_y >= height {
+        return Err(SolverError::UnsupportedSize { width, height });
     }
 
-    let x = match board_size {
-        8 => EIGHT_TOP_LEFT.0,
-        7 => SEVEN_TOP_LEFT.0,
-        6 => EIGHT_TOP_LEFT.0 + SQUARE_SIZE.0,
-        5 => SEVEN_TOP_LEFT.0 + SQUARE_SIZE.0,
-        _ => panic!("Board size is not 5-8")
-    } + i32::try_from(square_x).unwrap() * SQUARE_SIZE.0;
-
-    let y = match board_size {
-        8 => EIGHT_TOP_LEFT.1,
-        7 => SEVEN_TOP_LEFT.1,
-        6 => EIGHT_TOP_LEFT.1 + SQUARE_SIZE.1,
-        5 => SEVEN_TOP_LEFT.1 + SQUARE_SIZE.1,
-        _ => panic!("Board size is not 5-8")
-    } + i32::try_from(square_y).unwrap() * SQUARE_SIZE.1;
+    // The mouse works in screen coordinates, which are scaled differently from the captured window
+    let (x, y) = calibration.to_screen(calibration.grid(width, height).square_center(square_x, square_y));
 
     let mouse = Mouse::new();
-    mouse.move_to(x, y).expect("Unable to move mouse");
-    let delay = time::Duration::from_millis(20);
+    mouse.move_to(x, y).map_err(input_error)?;
+    let delay = time::Duration::from_millis(click_delay);
     thread::sleep(delay);
-    mouse.press(if flag {&Keys::RIGHT} else {&Keys::LEFT}).expect("Unable to press button");
+    mouse.press(if flag {&Keys::RIGHT} else {&Keys::LEFT}).map_err(input_error)?;
     thread::sleep(delay);
-    mouse.release(if flag {&Keys::RIGHT} else {&Keys::LEFT}).expect("Unable to release button");
+    mouse.release(if flag {&Keys::RIGHT} else {&Keys::LEFT}).map_err(input_error)?;
     thread::sleep(delay);
+    Ok(())
 }
 
-pub fn click_known_cells(known_cells:&KnownSquares, board_size:usize) -> bool{
-    let mut actions = false;
+fn input_error(err:Box<dyn std::error::Error + '_>) -> SolverError{
+    SolverError::Input(err.to_string())
+}
+
+// Clicks the safe cells open and flags the mines. Having nothing to click means the solver is stuck.
+pub fn click_known_cells(known_cells:&KnownSquares, calibration:&Calibration, width:usize, height:usize, click_delay:u64) -> Result<(), SolverError>{
+    if known_cells.safe.is_empty() && known_cells.mines.is_empty() {
+        return Err(SolverError::Stuck);
+    }
+
     for cell in &known_cells.safe{
-        click_square(cell % board_size, cell / board_size, board_size, false);
-        actions = true;
+        click_square(cell % width, cell / width, calibration, width, height, false, click_delay)?;
     }
     for cell in &known_cells.mines{
-        click_square(cell % board_size, cell / board_size, board_size, true);
-        actions = true;
+        click_square(cell % width, cell / width, calibration, width, height, true, click_delay)?;
     }
     let mouse = Mouse::new();
-    let ten_millis = time::Duration::from_millis(20);
-    thread::sleep(ten_millis);
-    mouse.move_to(50, 250).expect("Unable to move mouse");
-    actions
+    thread::sleep(time::Duration::from_millis(click_delay));
+    mouse.move_to(50, 250).map_err(input_error)?;
+    Ok(())
 }
 
-pub fn click_next_puzzle(){
+pub fn click_next_puzzle() -> Result<(), SolverError>{
     let mouse = Mouse::new();
-    mouse.move_to(1039, 858).expect("Unable to move mouse");
+    mouse.move_to(1039, 858).map_err(input_error)?;
     let delay = time::Duration::from_millis(25);
     thread::sleep(delay);
-    mouse.press(&Keys::LEFT).expect("Unable to press button");
+    mouse.press(&Keys::LEFT).map_err(input_error)?;
     thread::sleep(delay);
-    mouse.release(&Keys::LEFT).expect("Unable to release button");
+    mouse.release(&Keys::LEFT).map_err(input_error)?;
     thread::sleep(delay);
-    mouse.move_to(50, 250).expect("Unable to move mouse");
+    mouse.move_to(50, 250).map_err(input_error)?;
+    Ok(())
 }
\ No newline at end of file
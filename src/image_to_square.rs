@@ -1,7 +1,9 @@
 use image_compare::rgba_hybrid_compare;
 use image::RgbaImage;
+use std::fs;
 
 use crate::board::MinesweeperCell;
+use crate::error::SolverError;
 
 fn compare_images(img1: &RgbaImage, img2: &RgbaImage) -> f64{
     let score = rgba_hybrid_compare(img1, img2).unwrap();
@@ -13,97 +15,47 @@ pub struct ImgCellPair{
     cell:MinesweeperCell
 }
 
-pub fn get_img_cell_pairs() -> Vec<ImgCellPair>{
-    let mut res = Vec::new();
+impl ImgCellPair {
+    pub fn new(img:RgbaImage, cell:MinesweeperCell) -> ImgCellPair {
+        ImgCellPair{img, cell}
+    }
+}
 
-    res.push(
-        ImgCellPair{img:image::open("images/empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/mine.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Mine}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/0.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(0)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/1.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(1)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/2.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(2)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/3.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(3)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/question.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Question}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/4.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(4)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/5.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(5)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/6.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(6)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/7.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(7)}
-    );
-    res.push(
+pub fn get_img_cell_pairs() -> Vec<ImgCellPair>{
+    vec![
+        ImgCellPair{img:image::open("images/empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty},
+        ImgCellPair{img:image::open("images/mine.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Mine},
+        ImgCellPair{img:image::open("images/0.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(0)},
+        ImgCellPair{img:image::open("images/1.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(1)},
+        ImgCellPair{img:image::open("images/2.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(2)},
+        ImgCellPair{img:image::open("images/3.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(3)},
+        ImgCellPair{img:image::open("images/question.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Question},
+        ImgCellPair{img:image::open("images/4.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(4)},
+        ImgCellPair{img:image::open("images/5.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(5)},
+        ImgCellPair{img:image::open("images/6.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(6)},
+        ImgCellPair{img:image::open("images/7.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(7)},
         ImgCellPair{img:image::open("images/8.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(8)}
-    );
-
-    res
+    ]
 }
 
 pub fn get_img_cell_pairs_m() -> Vec<ImgCellPair>{
-    let mut res = Vec::new();
-
-    res.push(
-        ImgCellPair{img:image::open("images/empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/mine.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Mine}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/0.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(0)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/1.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(1)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/2.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(2)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/3.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(3)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/question.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Question}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/4.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(4)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/5.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(5)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/6.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(6)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/7.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(7)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/8.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(8)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/large_numbers/9.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(9)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/large_numbers/10.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(10)}
-    );
-    res.push(
+    let mut res = vec![
+        ImgCellPair{img:image::open("images/empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty},
+        ImgCellPair{img:image::open("images/mine.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Mine},
+        ImgCellPair{img:image::open("images/0.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(0)},
+        ImgCellPair{img:image::open("images/1.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(1)},
+        ImgCellPair{img:image::open("images/2.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(2)},
+        ImgCellPair{img:image::open("images/3.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(3)},
+        ImgCellPair{img:image::open("images/question.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Question},
+        ImgCellPair{img:image::open("images/4.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(4)},
+        ImgCellPair{img:image::open("images/5.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(5)},
+        ImgCellPair{img:image::open("images/6.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(6)},
+        ImgCellPair{img:image::open("images/7.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(7)},
+        ImgCellPair{img:image::open("images/8.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(8)},
+        ImgCellPair{img:image::open("images/large_numbers/9.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(9)},
+        ImgCellPair{img:image::open("images/large_numbers/10.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(10)},
         ImgCellPair{img:image::open("images/large_numbers/11.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(11)}
-    );
+    ];
 
     res.push(
         ImgCellPair{img:image::open("images/white/white_empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty}
@@ -155,143 +107,202 @@ pub fn get_img_cell_pairs_m() -> Vec<ImgCellPair>{
 }
 
 pub fn get_img_cell_pairs_l() -> Vec<ImgCellPair>{
-    let mut res = Vec::new();
+    vec![
+        ImgCellPair{img:image::open("images/empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty},
+        ImgCellPair{img:image::open("images/mine.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Mine},
+        ImgCellPair{img:image::open("images/0.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(0)},
+        ImgCellPair{img:image::open("images/1.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(1)},
+        ImgCellPair{img:image::open("images/2.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(2)},
+        ImgCellPair{img:image::open("images/3.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(3)},
+        ImgCellPair{img:image::open("images/question.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Question},
+        ImgCellPair{img:image::open("images/4.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(4)},
+        ImgCellPair{img:image::open("images/5.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(5)},
+        ImgCellPair{img:image::open("images/6.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(6)},
+        ImgCellPair{img:image::open("images/7.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(7)},
+        ImgCellPair{img:image::open("images/8.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(8)},
+        ImgCellPair{img:image::open("images/large_numbers/9.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(9)}
+    ]
+}
+
+pub fn get_img_cell_pairs_x_prime() -> Vec<ImgCellPair>{
+    vec![
+        ImgCellPair{img:image::open("images/empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty},
+        ImgCellPair{img:image::open("images/mine.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Mine},
+        ImgCellPair{img:image::open("images/0.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(0)},
+        ImgCellPair{img:image::open("images/1.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(1)},
+        ImgCellPair{img:image::open("images/2.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(2)},
+        ImgCellPair{img:image::open("images/3.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(3)},
+        ImgCellPair{img:image::open("images/question.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Question},
+        ImgCellPair{img:image::open("images/4.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(4)}
+    ]
+}
+
+pub fn get_img_cell_pairs_n() -> Vec<ImgCellPair>{
+    let mut res = vec![
+        ImgCellPair{img:image::open("images/empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty},
+        ImgCellPair{img:image::open("images/mine.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Mine},
+        ImgCellPair{img:image::open("images/0.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(0)},
+        ImgCellPair{img:image::open("images/1.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(1)},
+        ImgCellPair{img:image::open("images/2.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(2)},
+        ImgCellPair{img:image::open("images/3.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(3)},
+        ImgCellPair{img:image::open("images/question.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Question},
+        ImgCellPair{img:image::open("images/4.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(4)}
+    ];
 
     res.push(
-        ImgCellPair{img:image::open("images/empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/mine.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Mine}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/0.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(0)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/1.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(1)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/2.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(2)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/3.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(3)}
+        ImgCellPair{img:image::open("images/white/white_empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty}
     );
     res.push(
-        ImgCellPair{img:image::open("images/question.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Question}
+        ImgCellPair{img:image::open("images/white/white_mine.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Mine}
     );
     res.push(
-        ImgCellPair{img:image::open("images/4.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(4)}
+        ImgCellPair{img:image::open("images/white/white_0.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(0)}
     );
     res.push(
-        ImgCellPair{img:image::open("images/5.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(5)}
+        ImgCellPair{img:image::open("images/white/white_1.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(1)}
     );
     res.push(
-        ImgCellPair{img:image::open("images/6.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(6)}
+        ImgCellPair{img:image::open("images/white/white_2.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(2)}
     );
     res.push(
-        ImgCellPair{img:image::open("images/7.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(7)}
+        ImgCellPair{img:image::open("images/white/white_3.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(3)}
     );
     res.push(
-        ImgCellPair{img:image::open("images/8.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(8)}
+        ImgCellPair{img:image::open("images/white/white_question.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Question}
     );
     res.push(
-        ImgCellPair{img:image::open("images/large_numbers/9.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(9)}
+        ImgCellPair{img:image::open("images/white/white_4.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(4)}
     );
 
     res
 }
 
-pub fn get_img_cell_pairs_x_prime() -> Vec<ImgCellPair>{
-    let mut res = Vec::new();
+pub fn get_img_cell_pairs_e() -> Vec<ImgCellPair>{
+    let mut res = get_img_cell_pairs();
 
     res.push(
-        ImgCellPair{img:image::open("images/empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/mine.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Mine}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/0.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(0)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/1.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(1)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/2.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(2)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/3.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(3)}
+        ImgCellPair{img:image::open("images/large_numbers/9.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(9)}
     );
     res.push(
-        ImgCellPair{img:image::open("images/question.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Question}
+        ImgCellPair{img:image::open("images/large_numbers/10.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(10)}
     );
     res.push(
-        ImgCellPair{img:image::open("images/4.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(4)}
+        ImgCellPair{img:image::open("images/large_numbers/11.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(11)}
     );
 
     res
 }
 
-pub fn get_img_cell_pairs_n() -> Vec<ImgCellPair>{
-    let mut res = Vec::new();
+// Wall clues are read from images/wall, where every file is named after the runs it shows, like 1_3.jpg
+pub fn get_img_cell_pairs_w() -> Result<Vec<ImgCellPair>, SolverError>{
+    get_wall_pairs("images/wall")
+}
 
-    res.push(
-        ImgCellPair{img:image::open("images/empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/mine.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Mine}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/0.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(0)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/1.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(1)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/2.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(2)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/3.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(3)}
-    );
-    res.push(
+// Without a single wall template every wall would be taken for the closest plain square and the board read with
+// clues missing, so that is an error. Files that are not named after up to four runs are skipped.
+fn get_wall_pairs(dir:&str) -> Result<Vec<ImgCellPair>, SolverError>{
+    let no_walls = || SolverError::Capture(format!("no wall templates in {}", dir));
+    let mut res = vec![
+        ImgCellPair{img:image::open("images/empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty},
+        ImgCellPair{img:image::open("images/mine.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Mine},
         ImgCellPair{img:image::open("images/question.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Question}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/4.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(4)}
-    );
+    ];
 
-    res.push(
-        ImgCellPair{img:image::open("images/white/white_empty.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Empty}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/white/white_mine.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Mine}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/white/white_0.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(0)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/white/white_1.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(1)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/white/white_2.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(2)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/white/white_3.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(3)}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/white/white_question.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Question}
-    );
-    res.push(
-        ImgCellPair{img:image::open("images/white/white_4.jpg").unwrap().into_rgba8(), cell:MinesweeperCell::Number(4)}
-    );
+    let entries = fs::read_dir(dir).map_err(|_| no_walls())?;
+    let plain = res.len();
+
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        let runs = match path.file_stem().and_then(|stem| stem.to_str()).and_then(parse_wall_runs) {
+            Some(runs) => runs,
+            None => continue
+        };
+
+        if let Ok(img) = image::open(&path) {
+            res.push(ImgCellPair::new(img.into_rgba8(), MinesweeperCell::Wall(runs)));
+        }
+    }
+
+    if res.len() == plain {
+        return Err(no_walls());
+    }
+
+    Ok(res)
+}
+
+// The runs of a wall from a name like 1_3, longest first, or None when the name is not up to four runs
+fn parse_wall_runs(name:&str) -> Option<[usize; 4]> {
+    let runs = name.split('_').map(|run| run.parse::<usize>().ok()).collect::<Option<Vec<usize>>>()?;
+
+    let mut runs: Vec<usize> = runs.into_iter().filter(|run| *run > 0).collect();
+    if runs.len() > 4 {
+        return None;
+    }
+    runs.sort_by(|a, b| b.cmp(a));
+    runs.resize(4, 0);
+
+    runs.try_into().ok()
+}
+
+// Digits of the mine counter are read from images/counter, named 0.jpg to 9.jpg, with blank.jpg for an unused digit.
+// Without the folder there is nothing to compare against and the counter is not read. Files with other names or that
+// are not images are skipped.
+pub fn get_img_counter_pairs() -> Vec<ImgCellPair>{
+    let mut res = Vec::new();
+
+    let entries = match fs::read_dir("images/counter") {
+        Ok(entries) => entries,
+        Err(_) => return res
+    };
+
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        let cell = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some("blank") => MinesweeperCell::Empty,
+            Some(digit) => match digit.parse() {
+                Ok(digit) if digit < 10 => MinesweeperCell::Number(digit),
+                _ => continue
+            },
+            None => continue
+        };
+
+        if let Ok(img) = image::open(&path) {
+            res.push(ImgCellPair::new(img.into_rgba8(), cell));
+        }
+    }
 
     res
 }
 
-pub fn parse_cell(rgba_img:RgbaImage, img_cell_pairs:&Vec<ImgCellPair>) -> MinesweeperCell{
+pub fn parse_counter(digit_imgs:Vec<RgbaImage>, img_counter_pairs:&Vec<ImgCellPair>) -> Option<usize>{
+    if img_counter_pairs.is_empty() { return None };
+
+    let mut value = None;
+
+    for img in digit_imgs {
+        match parse_cell(img, img_counter_pairs)? {
+            MinesweeperCell::Number(digit) => value = Some(value.unwrap_or(0) * 10 + digit),
+            MinesweeperCell::Empty => if value.is_some() { return None },
+            _ => return None
+        }
+    }
+
+    value
+}
+
+// A square that looks this much like a known cell is taken to be that cell right away
+pub const MATCH_SCORE: f64 = 0.93;
+// Below this even the closest known cell is too different to trust, so the square is not recognised
+pub const MIN_MATCH_SCORE: f64 = 0.5;
+
+pub fn parse_cell(rgba_img:RgbaImage, img_cell_pairs:&Vec<ImgCellPair>) -> Option<MinesweeperCell>{
+    parse_cell_above(rgba_img, img_cell_pairs, MIN_MATCH_SCORE)
+}
+
+// Like parse_cell, but the closest known cell is only taken when it scores at least min_score
+pub fn parse_cell_above(rgba_img:RgbaImage, img_cell_pairs:&Vec<ImgCellPair>, min_score:f64) -> Option<MinesweeperCell>{
     for pair in img_cell_pairs{
         let score = compare_images(&rgba_img, &pair.img);
-        if score > 0.93{
-            return pair.cell.clone();
+        if score > MATCH_SCORE{
+            return Some(pair.cell);
         }
     }
 
@@ -310,5 +321,46 @@ pub fn parse_cell(rgba_img:RgbaImage, img_cell_pairs:&Vec<ImgCellPair>) -> Mines
 
     //println!("Matched: {} at score {}", highest_cell, highest_score);
 
-    highest_cell.clone()
-}
\ No newline at end of file
+    if highest_score < min_score {
+        return None;
+    }
+
+    Some(highest_cell)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn correct_wall_names() {
+        assert_eq!(parse_wall_runs("1_3"), Some([3, 1, 0, 0]));
+        assert_eq!(parse_wall_runs("0"), Some([0; 4]));
+        assert_eq!(parse_wall_runs("2_0_1_1_1"), Some([2, 1, 1, 1]));
+        assert_eq!(parse_wall_runs("1_1_1_1_1"), None);
+        assert_eq!(parse_wall_runs("screenshot"), None);
+        assert_eq!(parse_wall_runs(""), None);
+    }
+
+    #[test]
+    fn missing_wall_templates() {
+        let missing = "images/no_such_folder";
+        assert_eq!(get_wall_pairs(missing).err(), Some(SolverError::Capture(format!("no wall templates in {}", missing))));
+
+        // A folder with only files that are not named after runs has no wall templates either
+        assert_eq!(get_wall_pairs("images/white").err(), Some(SolverError::Capture("no wall templates in images/white".to_string())));
+    }
+
+    #[test]
+    fn correct_close_matches() {
+        // Stands in for a clue there is no template for, which only looks somewhat like the closest one
+        let template = RgbaImage::from_pixel(20, 20, Rgba([20, 20, 20, 255]));
+        let square = RgbaImage::from_fn(20, 20, |x, _| if x < 8 {Rgba([230, 230, 230, 255])} else {Rgba([20, 20, 20, 255])});
+        let pairs = vec![ImgCellPair::new(template.clone(), MinesweeperCell::Number(11))];
+
+        assert!(parse_cell(square.clone(), &pairs) == Some(MinesweeperCell::Number(11)));
+        assert!(parse_cell_above(square, &pairs, MATCH_SCORE).is_none());
+        assert!(parse_cell_above(template, &pairs, MATCH_SCORE) == Some(MinesweeperCell::Number(11)));
+    }
+}
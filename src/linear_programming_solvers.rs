@@ -1,35 +1,147 @@
-use crate::board::{Board, BoardIndexable, MinesweeperCell, NextToPolicy};
+use crate::board::{Board, BoardIndexable, MinesweeperCell, NextToPolicy, empty_board};
 use crate::algorithms::KnownSquares;
+use crate::error::SolverError;
+use crate::backtracking_solver::BacktrackingBackend;
 
-use highs::{Sense, HighsModelStatus, RowProblem};
+use highs::{Sense, HighsModelStatus, RowProblem, Col, Model};
 use std::fmt;
 use std::collections::{HashMap, HashSet};
 
 #[derive(Copy)]
 #[derive(Clone)]
 #[derive(PartialEq)]
-enum ConstraitType {
+pub enum ConstraitType {
     Equality,
     Minimum,
     Maximum,
     BlackWhiteEquality,
     OffByOne,
     DifferenceOfColors(f64),
+    // The cells hold value more mines than the cells in cells2
+    SameCount,
+    Connected(NextToPolicy),
+    ConnectedSafe(NextToPolicy),
+    ConnectedToEdge(NextToPolicy),
+    Dominoes,
+    Snake,
+    OneOfPatterns,
+    Eyesight([usize; 4]),
 }
 
 #[derive(Clone)]
-struct Constraint {
-    constrait_type : ConstraitType,
-    value: usize,
-    cells : Vec<usize>,
-    cells2 : Vec<usize>,
+pub struct Constraint {
+    pub constrait_type : ConstraitType,
+    pub value: i64,
+    pub cells : Vec<usize>,
+    pub cells2 : Vec<usize>,
+    // Numbers the rule needs that are not cells, like the bitmasks of OneOfPatterns and the weights of Eyesight
+    pub payload : Vec<usize>,
+    // The clue cell this constraint was read from, None for rules about the whole board
+    pub source : Option<usize>,
 }
 
 #[derive(Clone)]
 pub struct ConstraintSet {
-    cells : Vec<usize>,
-    constraints : Vec<Constraint>,
-    boardsize : usize
+    pub cells : Vec<usize>,
+    pub constraints : Vec<Constraint>,
+    pub width : usize,
+    pub height : usize
+}
+
+// A sum over positions in the cells of a set that has to land between min and max, added on top of its constraints
+#[derive(Clone)]
+pub struct ExtraRow {
+    pub cells: Vec<(usize, i64)>,
+    pub min: i64,
+    pub max: i64,
+}
+
+impl ExtraRow {
+    fn fixed(index:usize, value:bool) -> ExtraRow {
+        ExtraRow { cells: vec![(index, 1)], min: value as i64, max: value as i64 }
+    }
+
+    fn mine_count(cell_count:usize, min:i64, max:i64) -> ExtraRow {
+        ExtraRow { cells: (0..cell_count).map(|i| (i, 1)).collect(), min, max }
+    }
+}
+
+// The constraints of a set built into whatever the backend solves. Proving cells asks for many layouts of the same
+// set that only differ in a few extra rows, so the set is built once and each solve only adds those rows.
+pub trait PreparedSet {
+    // A layout that meets the constraints of the set and the extra rows, with one value per cell of the set.
+    // None only when the backend has shown there is no such layout; a solve that ends any other way is an error.
+    fn find_layout(&self, extra:&[ExtraRow]) -> Result<Option<Vec<bool>>, SolverError>;
+
+    // Fewest and most mines the cells of the set can hold, or None when the rules contradict each other.
+    // Narrows both ends down by asking for layouts with a bounded number of mines.
+    fn mine_count_range(&self) -> Result<Option<(i64, i64)>, SolverError> {
+        let (first, cell_count) = match self.find_layout(&[])? {
+            Some(layout) => (mines_in(&layout) as i64, layout.len()),
+            None => return Ok(None)
+        };
+
+        let (mut low, mut high) = (0, first);
+        while low < high {
+            let mid = (low + high) / 2;
+            match self.find_layout(&[ExtraRow::mine_count(cell_count, 0, mid)])? {
+                Some(layout) => high = mines_in(&layout) as i64,
+                None => low = mid + 1
+            }
+        }
+        let fewest = low;
+
+        let (mut low, mut high) = (first, cell_count as i64);
+        while low < high {
+            let mid = (low + high + 1) / 2;
+            match self.find_layout(&[ExtraRow::mine_count(cell_count, mid, cell_count as i64)])? {
+                Some(layout) => low = mines_in(&layout) as i64,
+                None => high = mid - 1
+            }
+        }
+
+        Ok(Some((fewest, low)))
+    }
+}
+
+// Finds layouts of mines that fit a set of constraints. Everything that proves, counts or weighs cells only asks for
+// layouts, so any backend here can stand in for another.
+pub trait SolverBackend {
+    // Builds the selected constraints of the set, ready to be asked for layouts
+    fn prepare(&self, constraints:&ConstraintSet, selected:&[&Constraint]) -> Box<dyn PreparedSet>;
+
+    // A single layout of the selected constraints, for when the set is only asked once
+    fn find_layout(&self, constraints:&ConstraintSet, selected:&[&Constraint], extra:&[ExtraRow]) -> Result<Option<Vec<bool>>, SolverError> {
+        self.prepare(constraints, selected).find_layout(extra)
+    }
+}
+
+// Runs the sets as mixed integer programs through the HiGHS library
+pub struct HighsBackend;
+
+#[derive(Copy)]
+#[derive(Clone)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Backend {
+    Highs,
+    Backtracking,
+}
+
+impl Backend {
+    pub fn from_name(name:&str) -> Option<Backend> {
+        match name.to_lowercase().as_str() {
+            "highs" => Some(Backend::Highs),
+            "native" | "backtracking" => Some(Backend::Backtracking),
+            _ => None
+        }
+    }
+
+    pub fn solver(&self) -> &'static dyn SolverBackend {
+        match self {
+            Backend::Highs => &HighsBackend,
+            Backend::Backtracking => &BacktrackingBackend,
+        }
+    }
 }
 
 #[derive(Copy)]
@@ -51,6 +163,14 @@ impl fmt::Display for ConstraitType {
             ConstraitType::BlackWhiteEquality => write!(f, "M="),
             ConstraitType::OffByOne => write!(f, "L="),
             ConstraitType::DifferenceOfColors(_) => write!(f, "N="),
+            ConstraitType::SameCount => write!(f, "B="),
+            ConstraitType::Connected(_) => write!(f, "C="),
+            ConstraitType::ConnectedSafe(_) => write!(f, "CS="),
+            ConstraitType::ConnectedToEdge(_) => write!(f, "CE="),
+            ConstraitType::Dominoes => write!(f, "D="),
+            ConstraitType::Snake => write!(f, "S="),
+            ConstraitType::OneOfPatterns => write!(f, "R="),
+            ConstraitType::Eyesight(_) => write!(f, "E="),
         }
     }
 }
@@ -78,7 +198,8 @@ pub fn combine_constraint_sets(set1:ConstraintSet, set2:ConstraintSet) -> Constr
             v.extend(set2.constraints);
             v
         }, 
-        boardsize: set1.boardsize 
+        width: set1.width,
+        height: set1.height
     }
 }
 
@@ -87,58 +208,55 @@ fn create_constraints_for_cell_v(id:usize, board:&Board) -> Vec<Constraint> {
         MinesweeperCell::Number(x) => {
             let next_to = board.get_next_to(id, NextToPolicy::EightAround);
             let (empty, bombs) = board.empty_and_mine_count(&next_to);
-            if empty.len() == 0 { return vec![] };
-            return vec![Constraint { constrait_type: ConstraitType::Equality, value: x - bombs, cells: empty, cells2: vec![] }];
+            if empty.is_empty() { return vec![] };
+            vec![Constraint { constrait_type: ConstraitType::Equality, value: x as i64 - bombs as i64, cells: empty, cells2: vec![], payload: vec![], source: Some(id) }]
         },
-        _ => return vec![]
+        _ => vec![]
     }
 }
 
-fn create_constraint_set_normal_mines(board:&Board) -> ConstraintSet{
+pub fn create_constraint_set_normal_mines(board:&Board) -> ConstraintSet{
     
-    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.size*board.size).collect());
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
 
     ConstraintSet {
         constraints:
-            (0..board.size*board.size).fold(vec![], 
+            (0..board.width*board.height).fold(vec![], 
                 |mut vec, id| 
                 {
                     vec.extend(create_constraints_for_cell_v(id, board));
                     vec
                 }),
-        boardsize: board.size,
+        width: board.width,
+        height: board.height,
         cells:empty_cells
     }
 }
 
-fn create_constraint_set_minecount(board:&Board) -> ConstraintSet{
-    let total_count:usize = match board.size{
-        5 => 10,
-        6 => 14,
-        7 => 20,
-        8 => 26,
-        _ => panic!("Bad board size")
-    };
-
-    let (empty_cells, mines_placed) = board.empty_and_mine_count(&(0..board.size*board.size).collect());
+pub fn create_constraint_set_minecount(board:&Board) -> ConstraintSet{
+    let (empty_cells, mines_placed) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
 
-    let mut constraints = vec![Constraint {constrait_type: ConstraitType::Equality, value: total_count-mines_placed, cells: empty_cells.clone(), cells2: vec![]}];
+    let constraints = match board.total_mines {
+        Some(total_count) => vec![Constraint {constrait_type: ConstraitType::Equality, value: total_count as i64 - mines_placed as i64, cells: empty_cells.clone(), cells2: vec![], payload: vec![], source: None }],
+        None => vec![]
+    };
 
     ConstraintSet {
-        constraints:constraints,
-        boardsize: board.size,
+        constraints,
+        width: board.width,
+        height: board.height,
         cells:empty_cells.clone()
     }
 }
 
 pub fn create_q_added_constraint_set(board:&Board) -> ConstraintSet{
-    let (empty_cells, mines_placed) = board.empty_and_mine_count(&(0..board.size*board.size).collect());
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
 
     let mut constraints = vec![];
 
-    for x in 0..board.size - 1{
-        for y in 0..board.size - 1{
-            let cells = vec![x + y * board.size, x+1 + y * board.size, x + (y+1) * board.size, x+1 + (y+1) * board.size];
+    for x in 0..board.width - 1{
+        for y in 0..board.height - 1{
+            let cells = vec![x + y * board.width, x+1 + y * board.width, x + (y+1) * board.width, x+1 + (y+1) * board.width];
             let (empty, mines) = board.empty_and_mine_count(&cells);
             if mines == 0 {
                 constraints.push(
@@ -146,7 +264,9 @@ pub fn create_q_added_constraint_set(board:&Board) -> ConstraintSet{
                         constrait_type: ConstraitType::Minimum,
                         value: 1,
                         cells: empty,
-                        cells2: vec![]
+                        cells2: vec![],
+                        payload: vec![],
+                        source: None
                     }
                 )
             }
@@ -154,8 +274,9 @@ pub fn create_q_added_constraint_set(board:&Board) -> ConstraintSet{
     }
 
     ConstraintSet {
-        constraints:constraints,
-        boardsize: board.size,
+        constraints,
+        width: board.width,
+        height: board.height,
         cells:empty_cells
     } 
 }
@@ -166,25 +287,26 @@ fn create_constraints_for_cell_m(id:usize, board:&Board) -> Vec<Constraint> {
             let next_to = board.get_next_to(id, NextToPolicy::EightAround);
             let (black, white, bombs) = board.black_white_m_minecount(&next_to);
             if black.len() + white.len() == 0 { return vec![] };
-            return vec![Constraint { constrait_type: ConstraitType::BlackWhiteEquality, value: x - bombs, cells: black, cells2: white }];
+            vec![Constraint { constrait_type: ConstraitType::BlackWhiteEquality, value: x as i64 - bombs as i64, cells: black, cells2: white, payload: vec![], source: Some(id) }]
         },
-        _ => return vec![]
+        _ => vec![]
     }
 }
 
 pub fn create_constraint_set_multiple_mines(board:&Board) -> ConstraintSet{
     
-    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.size*board.size).collect());
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
 
     ConstraintSet {
         constraints:
-            (0..board.size*board.size).fold(vec![], 
+            (0..board.width*board.height).fold(vec![], 
                 |mut vec, id| 
                 {
                     vec.extend(create_constraints_for_cell_m(id, board));
                     vec
                 }),
-        boardsize: board.size,
+        width: board.width,
+        height: board.height,
         cells:empty_cells
     }
 }
@@ -194,87 +316,72 @@ fn create_constraints_for_cell_l(id:usize, board:&Board) -> Vec<Constraint> {
         MinesweeperCell::Number(x) => {
             let next_to = board.get_next_to(id, NextToPolicy::EightAround);
             let (empty, bombs) = board.empty_and_mine_count(&next_to);
-            if empty.len() == 0 { return vec![] };
+            if empty.is_empty() { return vec![] };
             if x == 0 {
-                return vec![Constraint { constrait_type: ConstraitType::Equality, value: 1 - bombs, cells: empty, cells2: vec![] }]
+                return vec![Constraint { constrait_type: ConstraitType::Equality, value: 1 - bombs as i64, cells: empty, cells2: vec![], payload: vec![], source: Some(id) }]
             }
             if bombs > x {
-                return vec![Constraint { constrait_type: ConstraitType::Equality, value: 0, cells: empty, cells2: vec![] }]
+                return vec![Constraint { constrait_type: ConstraitType::Equality, value: 0, cells: empty, cells2: vec![], payload: vec![], source: Some(id) }]
             }
-            return vec![Constraint { constrait_type: ConstraitType::OffByOne, value: x - bombs, cells: empty, cells2: vec![] }];
+            vec![Constraint { constrait_type: ConstraitType::OffByOne, value: x as i64 - bombs as i64, cells: empty, cells2: vec![], payload: vec![], source: Some(id) }]
         },
-        _ => return vec![]
+        _ => vec![]
     }
 }
 
 pub fn create_constraint_set_liar_mines(board:&Board) -> ConstraintSet{
     
-    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.size*board.size).collect());
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
 
     ConstraintSet {
         constraints:
-            (0..board.size*board.size).fold(vec![], 
+            (0..board.width*board.height).fold(vec![], 
                 |mut vec, id| 
                 {
                     vec.extend(create_constraints_for_cell_l(id, board));
                     vec
                 }),
-        boardsize: board.size,
+        width: board.width,
+        height: board.height,
         cells:empty_cells
     }
 }
 
 
-pub fn create_constraint_set_minecount_b(board:&Board) -> ConstraintSet{
-    let total_count:usize = match board.size{
-        5 => 10,
-        6 => 12,
-        7 => 21,
-        8 => 24,
-        _ => panic!("Bad board size")
-    };
+// Every row holds as many mines as every other row, and every column as many as every other column.
+// A total that is shared out evenly fixes the count of each row and column, otherwise they are only tied to each other
+// and a total that cannot be shared out evenly leaves the board infeasible instead of rounding the counts down.
+pub fn create_b_added_constraint_set(board:&Board) -> ConstraintSet{
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
 
-    let (empty_cells, mines_placed) = board.empty_and_mine_count(&(0..board.size*board.size).collect());
+    let rows: Vec<Vec<usize>> = (0..board.height).map(|y| (0..board.width).map(|x| x+y*board.width).collect()).collect();
+    let columns: Vec<Vec<usize>> = (0..board.width).map(|x| (0..board.height).map(|y| (y*board.width)+x).collect()).collect();
 
-    let mut constraints = vec![Constraint {constrait_type: ConstraitType::Equality, value: total_count-mines_placed, cells: empty_cells.clone(), cells2: vec![]}];
+    let even_total = board.total_mines.filter(|total| total % board.width == 0 && total % board.height == 0);
+
+    let mut constraints = balance_lines(board, &rows, even_total.map(|total| total / board.height));
+    constraints.extend(balance_lines(board, &columns, even_total.map(|total| total / board.width)));
 
     ConstraintSet {
-        constraints:constraints,
-        boardsize: board.size,
-        cells:empty_cells.clone()
+        constraints,
+        width: board.width,
+        height: board.height,
+        cells:empty_cells
     }
 }
 
-pub fn create_b_added_constraint_set(board:&Board) -> ConstraintSet{
-    let (empty_cells, mines_placed) = board.empty_and_mine_count(&(0..board.size*board.size).collect());
-
-    let mines_per_row = match board.size {
-        5 => 2,
-        6 => 2,
-        7 => 3,
-        8 => 3,
-        _ => panic!("Bad board size")
-    };
-
-    let mut constraints = vec![];
-
-    for i in 0..board.size{
-        //Rows
-        let (empty_cells, mines_placed) = board.empty_and_mine_count(&(0..board.size).map(|x|x+i*board.size).collect());
+// Gives every line the same number of mines, either a known count or as many as the first line
+fn balance_lines(board:&Board, lines:&[Vec<usize>], per_line:Option<usize>) -> Vec<Constraint> {
+    let (first_empty, first_mines) = board.empty_and_mine_count(&lines[0]);
 
-        constraints.push(Constraint { constrait_type: ConstraitType::Equality, value: mines_per_row - mines_placed, cells: empty_cells, cells2: vec![] });
-
-        //Columns
-        let (empty_cells, mines_placed) = board.empty_and_mine_count(&(0..board.size).map(|x|(x*board.size)+i).collect());
-
-        constraints.push(Constraint { constrait_type: ConstraitType::Equality, value: mines_per_row - mines_placed, cells: empty_cells, cells2: vec![] });
-    }
-
-    ConstraintSet {
-        constraints:constraints,
-        boardsize: board.size,
-        cells:empty_cells
-    } 
+    lines.iter().enumerate().filter_map(|(i, line)| {
+        let (empty, mines) = board.empty_and_mine_count(line);
+        match per_line {
+            Some(count) => Some(Constraint { constrait_type: ConstraitType::Equality, value: count as i64 - mines as i64, cells: empty, cells2: vec![], payload: vec![], source: None }),
+            None if i == 0 => None,
+            None => Some(Constraint { constrait_type: ConstraitType::SameCount, value: first_mines as i64 - mines as i64, cells: empty, cells2: first_empty.clone(), payload: vec![], source: None })
+        }
+    }).collect()
 }
 
 fn create_constraints_for_cell_n(id:usize, board:&Board) -> Vec<Constraint> {
@@ -283,25 +390,26 @@ fn create_constraints_for_cell_n(id:usize, board:&Board) -> Vec<Constraint> {
             let next_to = board.get_next_to(id, NextToPolicy::EightAround);
             let (black, white, black_bombs, white_bomds) = board.black_white_split_minecount(&next_to);
             if black.len() + white.len() == 0 { return vec![] };
-            return vec![Constraint { constrait_type: ConstraitType::DifferenceOfColors(black_bombs as f64-white_bomds as f64), value: x, cells: black, cells2: white }];
+            vec![Constraint { constrait_type: ConstraitType::DifferenceOfColors(black_bombs as f64-white_bomds as f64), value: x as i64, cells: black, cells2: white, payload: vec![], source: Some(id) }]
         },
-        _ => return vec![]
+        _ => vec![]
     }
 }
 
 pub fn create_constraint_set_negation_mines(board:&Board) -> ConstraintSet{
     
-    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.size*board.size).collect());
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
 
     ConstraintSet {
         constraints:
-            (0..board.size*board.size).fold(vec![], 
+            (0..board.width*board.height).fold(vec![], 
                 |mut vec, id| 
                 {
                     vec.extend(create_constraints_for_cell_n(id, board));
                     vec
                 }),
-        boardsize: board.size,
+        width: board.width,
+        height: board.height,
         cells:empty_cells
     }
 }
@@ -311,149 +419,590 @@ fn create_constraints_for_cell_x(id:usize, board:&Board) -> Vec<Constraint> {
         MinesweeperCell::Number(x) => {
             let next_to = board.get_next_to(id, NextToPolicy::XScape);
             let (empty, bombs) = board.empty_and_mine_count(&next_to);
-            if empty.len() == 0 { return vec![] };
-            return vec![Constraint { constrait_type: ConstraitType::Equality, value: x - bombs, cells: empty, cells2: vec![] }];
+            if empty.is_empty() { return vec![] };
+            vec![Constraint { constrait_type: ConstraitType::Equality, value: x as i64 - bombs as i64, cells: empty, cells2: vec![], payload: vec![], source: Some(id) }]
         },
-        _ => return vec![]
+        _ => vec![]
     }
 }
 
 pub fn create_constraint_set_cross_mines(board:&Board) -> ConstraintSet{
     
-    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.size*board.size).collect());
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
 
     ConstraintSet {
         constraints:
-            (0..board.size*board.size).fold(vec![], 
+            (0..board.width*board.height).fold(vec![], 
                 |mut vec, id| 
                 {
                     vec.extend(create_constraints_for_cell_x(id, board));
                     vec
                 }),
-        boardsize: board.size,
+        width: board.width,
+        height: board.height,
+        cells:empty_cells
+    }
+}
+
+// Clues that look at the ring around them: try every way to fill in the undecided cells of the ring and keep
+// the ones where the ring fits the clue. The fillings that are left are stored in the payload as bitmasks over cells.
+fn create_ring_pattern_constraints(id:usize, board:&Board, fits:impl Fn(&Vec<bool>) -> bool) -> Vec<Constraint> {
+    let ring = board.get_ring_around(id);
+    let empty: Vec<usize> = ring.iter().flatten().filter(|i| board[**i] == MinesweeperCell::Empty).cloned().collect();
+    if empty.is_empty() { return vec![] };
+
+    let patterns = (0..1usize << empty.len()).filter(|mask| {
+        let filled = ring.iter().map(|cell| match cell {
+            Some(i) => match board[*i] {
+                MinesweeperCell::Mine => true,
+                MinesweeperCell::Empty => (mask >> empty.iter().position(|e| e == i).unwrap()) & 1 == 1,
+                _ => false
+            },
+            None => false
+        }).collect();
+        fits(&filled)
+    }).collect();
+
+    vec![Constraint { constrait_type: ConstraitType::OneOfPatterns, value: 0, cells: empty, cells2: vec![], payload: patterns, source: Some(id) }]
+}
+
+// Lengths of the separate groups of mines going around the ring, longest first
+fn ring_runs(ring:&[bool]) -> Vec<usize> {
+    let start = match ring.iter().position(|mine| !mine) {
+        Some(start) => start,
+        None => return vec![ring.len()]
+    };
+
+    let mut runs = vec![];
+    let mut current = 0;
+    for i in 1..=ring.len() {
+        if ring[(start + i) % ring.len()] {
+            current += 1;
+        } else if current > 0 {
+            runs.push(current);
+            current = 0;
+        }
+    }
+
+    runs.sort_by(|a, b| b.cmp(a));
+    runs
+}
+
+fn create_constraints_for_cell_w(id:usize, board:&Board) -> Vec<Constraint> {
+    match board[id] {
+        MinesweeperCell::Wall(runs) => create_ring_pattern_constraints(id, board, |ring| {
+            let mut found = [0; 4];
+            for (i, run) in ring_runs(ring).into_iter().enumerate() {
+                found[i] = run;
+            }
+            found == runs
+        }),
+        _ => vec![]
+    }
+}
+
+pub fn create_constraint_set_wall_mines(board:&Board) -> ConstraintSet{
+    
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
+
+    ConstraintSet {
+        constraints:
+            (0..board.width*board.height).fold(vec![], 
+                |mut vec, id| 
+                {
+                    vec.extend(create_constraints_for_cell_w(id, board));
+                    vec
+                }),
+        width: board.width,
+        height: board.height,
+        cells:empty_cells
+    }
+}
+
+fn create_constraints_for_cell_w_prime(id:usize, board:&Board) -> Vec<Constraint> {
+    match board[id] {
+        MinesweeperCell::Number(x) => create_ring_pattern_constraints(id, board, |ring| *ring_runs(ring).first().unwrap_or(&0) == x),
+        _ => vec![]
+    }
+}
+
+pub fn create_constraint_set_longest_run_mines(board:&Board) -> ConstraintSet{
+    
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
+
+    ConstraintSet {
+        constraints:
+            (0..board.width*board.height).fold(vec![], 
+                |mut vec, id| 
+                {
+                    vec.extend(create_constraints_for_cell_w_prime(id, board));
+                    vec
+                }),
+        width: board.width,
+        height: board.height,
+        cells:empty_cells
+    }
+}
+
+fn create_constraints_for_cell_p(id:usize, board:&Board) -> Vec<Constraint> {
+    match board[id] {
+        MinesweeperCell::Number(x) => create_ring_pattern_constraints(id, board, |ring| ring_runs(ring).len() == x),
+        _ => vec![]
+    }
+}
+
+pub fn create_constraint_set_partition_mines(board:&Board) -> ConstraintSet{
+    
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
+
+    ConstraintSet {
+        constraints:
+            (0..board.width*board.height).fold(vec![], 
+                |mut vec, id| 
+                {
+                    vec.extend(create_constraints_for_cell_p(id, board));
+                    vec
+                }),
+        width: board.width,
+        height: board.height,
+        cells:empty_cells
+    }
+}
+
+// The clue sees itself and every safe cell up to the first mine in each direction. The undecided cells of the
+// four directions go into cells one direction after another, with the amount per direction kept in the type.
+// The payload holds how many visible cells each undecided cell accounts for: itself and the revealed cells behind it.
+fn create_constraints_for_cell_e(id:usize, board:&Board) -> Vec<Constraint> {
+    match board[id] {
+        MinesweeperCell::Number(x) => {
+            let mut seen = 1;
+            let mut cells = vec![];
+            let mut weights: Vec<usize> = vec![];
+            let mut per_direction = [0; 4];
+
+            for (direction, line) in board.get_lines_of_sight(id).into_iter().enumerate() {
+                for i in line {
+                    match board[i] {
+                        MinesweeperCell::Mine => break,
+                        MinesweeperCell::Empty => {
+                            cells.push(i);
+                            weights.push(1);
+                            per_direction[direction] += 1;
+                        },
+                        _ => if per_direction[direction] == 0 {seen += 1} else {*weights.last_mut().unwrap() += 1}
+                    }
+                }
+            }
+
+            if cells.is_empty() { return vec![] };
+            vec![Constraint { constrait_type: ConstraitType::Eyesight(per_direction), value: x as i64 - seen as i64, cells, cells2: vec![], payload: weights, source: Some(id) }]
+        },
+        _ => vec![]
+    }
+}
+
+pub fn create_constraint_set_eyesight_mines(board:&Board) -> ConstraintSet{
+    
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
+
+    ConstraintSet {
+        constraints:
+            (0..board.width*board.height).fold(vec![], 
+                |mut vec, id| 
+                {
+                    vec.extend(create_constraints_for_cell_e(id, board));
+                    vec
+                }),
+        width: board.width,
+        height: board.height,
+        cells:empty_cells
+    }
+}
+
+fn create_constraints_for_cell_k(id:usize, board:&Board) -> Vec<Constraint> {
+    match board[id] {
+        MinesweeperCell::Number(x) => {
+            let next_to = board.get_next_to(id, NextToPolicy::Knight);
+            let (empty, bombs) = board.empty_and_mine_count(&next_to);
+            if empty.is_empty() { return vec![] };
+            vec![Constraint { constrait_type: ConstraitType::Equality, value: x as i64 - bombs as i64, cells: empty, cells2: vec![], payload: vec![], source: Some(id) }]
+        },
+        _ => vec![]
+    }
+}
+
+pub fn create_constraint_set_knight_mines(board:&Board) -> ConstraintSet{
+    
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
+
+    ConstraintSet {
+        constraints:
+            (0..board.width*board.height).fold(vec![], 
+                |mut vec, id| 
+                {
+                    vec.extend(create_constraints_for_cell_k(id, board));
+                    vec
+                }),
+        width: board.width,
+        height: board.height,
         cells:empty_cells
     }
 }
 
 pub fn create_t_added_constraint_set(board:&Board) -> ConstraintSet{
-    let (empty_cells, mines_placed) = board.empty_and_mine_count(&(0..board.size*board.size).collect());
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
 
 
     let mut constraints = vec![];
 
+    // A board narrower or lower than three has no lines of three that way, so those loops are skipped
+
     //Horizontal
-    for y in 0..board.size{
-        for x in 0..board.size-2{
-            let spaces = vec![y*board.size+x, y*board.size+x+1, y*board.size+x+2];
+    for y in 0..board.height{
+        for x in 0..board.width.saturating_sub(2){
+            let spaces = vec![y*board.width+x, y*board.width+x+1, y*board.width+x+2];
             let (empty, mines) = board.empty_and_mine_count(&spaces);
             if empty.len() + mines < 3 {continue};
-            constraints.push(Constraint { constrait_type: ConstraitType::Maximum, value: 2 - mines, cells: empty, cells2: vec![] });
+            constraints.push(Constraint { constrait_type: ConstraitType::Maximum, value: 2 - mines as i64, cells: empty, cells2: vec![], payload: vec![], source: None });
         }
     }
 
     //Vertical
-    for y in 0..board.size-2{
-        for x in 0..board.size{
-            let spaces = vec![y*board.size+x, (y+1)*board.size+x, (y+2)*board.size+x];
+    for y in 0..board.height.saturating_sub(2){
+        for x in 0..board.width{
+            let spaces = vec![y*board.width+x, (y+1)*board.width+x, (y+2)*board.width+x];
             let (empty, mines) = board.empty_and_mine_count(&spaces);
             if empty.len() + mines < 3 {continue};
-            constraints.push(Constraint { constrait_type: ConstraitType::Maximum, value: 2 - mines, cells: empty, cells2: vec![] });
+            constraints.push(Constraint { constrait_type: ConstraitType::Maximum, value: 2 - mines as i64, cells: empty, cells2: vec![], payload: vec![], source: None });
         }
     }
 
     //Top-left to Bottom-right
-    for y in 0..board.size-2{
-        for x in 0..board.size-2{
-            let spaces = vec![y*board.size+x, (y+1)*board.size+x+1, (y+2)*board.size+x+2];
+    for y in 0..board.height.saturating_sub(2){
+        for x in 0..board.width.saturating_sub(2){
+            let spaces = vec![y*board.width+x, (y+1)*board.width+x+1, (y+2)*board.width+x+2];
             let (empty, mines) = board.empty_and_mine_count(&spaces);
             if empty.len() + mines < 3 {continue};
-            constraints.push(Constraint { constrait_type: ConstraitType::Maximum, value: 2 - mines, cells: empty, cells2: vec![] });
+            constraints.push(Constraint { constrait_type: ConstraitType::Maximum, value: 2 - mines as i64, cells: empty, cells2: vec![], payload: vec![], source: None });
         }
     }
 
     //Top-right to Bottom-left
-    for y in 0..board.size-2{
-        for x in 2..board.size{
-            let spaces = vec![y*board.size+x, (y+1)*board.size+x-1, (y+2)*board.size+x-2];
+    for y in 0..board.height.saturating_sub(2){
+        for x in 2..board.width{
+            let spaces = vec![y*board.width+x, (y+1)*board.width+x-1, (y+2)*board.width+x-2];
             let (empty, mines) = board.empty_and_mine_count(&spaces);
             if empty.len() + mines < 3 {continue};
-            constraints.push(Constraint { constrait_type: ConstraitType::Maximum, value: 2 - mines, cells: empty, cells2: vec![] });
+            constraints.push(Constraint { constrait_type: ConstraitType::Maximum, value: 2 - mines as i64, cells: empty, cells2: vec![], payload: vec![], source: None });
         }
     }
 
     ConstraintSet {
-        constraints:constraints,
-        boardsize: board.size,
+        constraints,
+        width: board.width,
+        height: board.height,
         cells:empty_cells
     } 
 }
 
+pub fn create_c_added_constraint_set(board:&Board) -> ConstraintSet{
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
+
+    let placed_mines: Vec<usize> = (0..board.width*board.height).filter(|i| board[*i] == MinesweeperCell::Mine).collect();
+
+    ConstraintSet {
+        constraints:vec![Constraint { constrait_type: ConstraitType::Connected(NextToPolicy::EightAround), value: 0, cells: empty_cells.clone(), cells2: placed_mines, payload: vec![], source: None }],
+        width: board.width,
+        height: board.height,
+        cells:empty_cells
+    }
+}
+
+pub fn create_o_added_constraint_set(board:&Board) -> ConstraintSet{
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
+
+    let placed_mines: Vec<usize> = (0..board.width*board.height).filter(|i| board[*i] == MinesweeperCell::Mine).collect();
+    let revealed: Vec<usize> = (0..board.width*board.height).filter(|i| board[*i] != MinesweeperCell::Mine && board[*i] != MinesweeperCell::Empty).collect();
+
+    ConstraintSet {
+        constraints:vec![
+            Constraint { constrait_type: ConstraitType::ConnectedToEdge(NextToPolicy::XSmall), value: 0, cells: empty_cells.clone(), cells2: placed_mines, payload: vec![], source: None },
+            Constraint { constrait_type: ConstraitType::ConnectedSafe(NextToPolicy::XSmall), value: 0, cells: empty_cells.clone(), cells2: revealed, payload: vec![], source: None },
+        ],
+        width: board.width,
+        height: board.height,
+        cells:empty_cells
+    }
+}
+
+pub fn create_d_added_constraint_set(board:&Board) -> ConstraintSet{
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
+
+    let placed_mines: Vec<usize> = (0..board.width*board.height).filter(|i| board[*i] == MinesweeperCell::Mine).collect();
+
+    ConstraintSet {
+        constraints:vec![Constraint { constrait_type: ConstraitType::Dominoes, value: 0, cells: empty_cells.clone(), cells2: placed_mines, payload: vec![], source: None }],
+        width: board.width,
+        height: board.height,
+        cells:empty_cells
+    }
+}
+
+pub fn create_s_added_constraint_set(board:&Board) -> ConstraintSet{
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
+
+    let placed_mines: Vec<usize> = (0..board.width*board.height).filter(|i| board[*i] == MinesweeperCell::Mine).collect();
+
+    ConstraintSet {
+        constraints:vec![
+            Constraint { constrait_type: ConstraitType::Snake, value: 0, cells: empty_cells.clone(), cells2: placed_mines.clone(), payload: vec![], source: None },
+            Constraint { constrait_type: ConstraitType::Connected(NextToPolicy::XSmall), value: 0, cells: empty_cells.clone(), cells2: placed_mines, payload: vec![], source: None },
+        ],
+        width: board.width,
+        height: board.height,
+        cells:empty_cells
+    }
+}
+
 fn create_constraints_for_cell_x_prime(id:usize, board:&Board) -> Vec<Constraint> {
     match board[id] {
         MinesweeperCell::Number(x) => {
             let next_to = board.get_next_to(id, NextToPolicy::XSmall);
             let (empty, bombs) = board.empty_and_mine_count(&next_to);
-            if empty.len() == 0 { return vec![] };
-            return vec![Constraint { constrait_type: ConstraitType::Equality, value: x - bombs, cells: empty, cells2: vec![] }];
+            if empty.is_empty() { return vec![] };
+            vec![Constraint { constrait_type: ConstraitType::Equality, value: x as i64 - bombs as i64, cells: empty, cells2: vec![], payload: vec![], source: Some(id) }]
         },
-        _ => return vec![]
+        _ => vec![]
     }
 }
 
-fn create_constraint_set_mini_cross_mines(board:&Board) -> ConstraintSet{
+pub fn create_constraint_set_mini_cross_mines(board:&Board) -> ConstraintSet{
     
-    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.size*board.size).collect());
+    let (empty_cells, _) = board.empty_and_mine_count(&(0..board.width*board.height).collect::<Vec<_>>());
 
     ConstraintSet {
         constraints:
-            (0..board.size*board.size).fold(vec![], 
+            (0..board.width*board.height).fold(vec![], 
                 |mut vec, id| 
                 {
                     vec.extend(create_constraints_for_cell_x_prime(id, board));
                     vec
                 }),
-        boardsize: board.size,
+        width: board.width,
+        height: board.height,
         cells:empty_cells
     }
 }
 
-pub fn create_constraint_set_v(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_minecount(board), create_constraint_set_normal_mines(board))
-}
+// Single commodity flow: a root sends a unit of flow to every cell of the region, and flow may only
+// travel between neighbouring cells of the region, so every one of them has to be reachable from the root.
+// cells are the undecided cells, cells2 the cells that are already known to be in the region.
+// For ConnectedSafe the region is the safe cells instead of the mines, for ConnectedToEdge the flow enters
+// from outside the board through the edge cells instead of from a single root.
+fn add_connected_rows(pb:&mut RowProblem, colums:&[Col], lookup:&HashMap<usize, usize>, constraint:&Constraint, width:usize, height:usize){
+    let (policy, count_safe, to_edge) = match constraint.constrait_type {
+        ConstraitType::Connected(policy) => (policy, false, false),
+        ConstraitType::ConnectedSafe(policy) => (policy, true, false),
+        ConstraitType::ConnectedToEdge(policy) => (policy, false, true),
+        _ => panic!("{} is not a rule about a connected region", constraint.constrait_type)
+    };
+    let grid = empty_board(width, height);
 
-pub fn create_constraint_set_q(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_v(board), create_q_added_constraint_set(board))
-}
+    let nodes: Vec<usize> = constraint.cells.iter().chain(constraint.cells2.iter()).cloned().collect();
+    let node_set: HashSet<usize> = nodes.iter().cloned().collect();
+    let capacity = nodes.len() as f64;
 
-pub fn create_constraint_set_m(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_minecount(board), create_constraint_set_multiple_mines(board))
-}
+    // A cell is in the region when base + factor * column == 1
+    let (base, factor) = if count_safe {(1., -1.)} else {(0., 1.)};
+    let region_var = |id:usize| lookup.get(&id).map(|i| colums[*i]);
 
-pub fn create_constraint_set_l(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_minecount(board), create_constraint_set_liar_mines(board))
-}
+    let mut balance: HashMap<usize, Vec<(Col, f64)>> = nodes.iter().map(|id| (*id, vec![])).collect();
 
-pub fn create_constraint_set_b(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_normal_mines(board), combine_constraint_sets(create_constraint_set_minecount_b(board), create_b_added_constraint_set(board)))
-}
+    for from in &nodes {
+        for to in grid.get_next_to(*from, policy) {
+            if !node_set.contains(&to) {continue};
+
+            let arc = pb.add_column(0., 0..);
+            for end in [*from, to] {
+                if let Some(x) = region_var(end) {
+                    pb.add_row(..=(capacity*base), vec![(arc, 1.), (x, -capacity*factor)]);
+                }
+            }
+            balance.get_mut(from).unwrap().push((arc, -1.));
+            balance.get_mut(&to).unwrap().push((arc, 1.));
+        }
+    }
+
+    if to_edge {
+        let on_edge = |id:usize| id < width || id >= width*(height-1) || id % width == 0 || (id+1) % width == 0;
+        for id in nodes.iter().filter(|id| on_edge(**id)) {
+            let supply = pb.add_column(0., 0..);
+            balance.get_mut(id).unwrap().push((supply, 1.));
+        }
+    } else {
+        match constraint.cells2.first() {
+            Some(root) => {
+                let supply = pb.add_column(0., 0..);
+                balance.get_mut(root).unwrap().push((supply, 1.));
+            }
+            None => {
+                let mut roots = vec![];
+                for id in &constraint.cells {
+                    let x = region_var(*id).unwrap();
+                    let root = pb.add_integer_column(0., 0..1);
+                    let supply = pb.add_column(0., 0..);
+                    pb.add_row(..=base, vec![(root, 1.), (x, -factor)]);
+                    pb.add_row(..=0., vec![(supply, 1.), (root, -capacity)]);
+                    balance.get_mut(id).unwrap().push((supply, 1.));
+                    roots.push((root, 1.));
+                }
+                pb.add_row(..=1., roots);
+            }
+        }
+    }
 
-pub fn create_constraint_set_n(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_minecount(board), create_constraint_set_negation_mines(board))
+    for id in nodes {
+        let mut row = balance.remove(&id).unwrap();
+        match region_var(id) {
+            Some(x) => {
+                row.push((x, -factor));
+                pb.add_row(base..=base, row);
+            }
+            None => pb.add_row(1.0..=1.0, row)
+        }
+    }
 }
 
-pub fn create_constraint_set_x(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_minecount(board), create_constraint_set_cross_mines(board))
+// Every orthogonal pair of possible mines gets a domino column. Each mine has to be covered by exactly one
+// domino, and two orthogonally touching mines have to be the two halves of the same domino.
+// cells are the undecided cells, cells2 the mines that are already placed.
+fn add_domino_rows(pb:&mut RowProblem, colums:&[Col], lookup:&HashMap<usize, usize>, constraint:&Constraint, width:usize, height:usize){
+    let grid = empty_board(width, height);
+
+    let nodes: Vec<usize> = constraint.cells.iter().chain(constraint.cells2.iter()).cloned().collect();
+    let node_set: HashSet<usize> = nodes.iter().cloned().collect();
+
+    let mine_var = |id:usize| lookup.get(&id).map(|i| colums[*i]);
+
+    let mut covering: HashMap<usize, Vec<(Col, f64)>> = nodes.iter().map(|id| (*id, vec![])).collect();
+
+    for first in &nodes {
+        for second in grid.get_next_to(*first, NextToPolicy::XSmall) {
+            if second < *first || !node_set.contains(&second) {continue};
+
+            let domino = pb.add_integer_column(0., 0..1);
+            covering.get_mut(first).unwrap().push((domino, 1.));
+            covering.get_mut(&second).unwrap().push((domino, 1.));
+
+            let mut touching = vec![(domino, -1.)];
+            let mut placed = 0.;
+            for end in [*first, second] {
+                match mine_var(end) {
+                    Some(x) => touching.push((x, 1.)),
+                    None => placed += 1.
+                }
+            }
+            pb.add_row(..=(1. - placed), touching);
+        }
+    }
+
+    for id in nodes {
+        let mut row = covering.remove(&id).unwrap();
+        match mine_var(id) {
+            Some(x) => {
+                row.push((x, -1.));
+                pb.add_row(0.0..=0.0, row);
+            }
+            None => pb.add_row(1.0..=1.0, row)
+        }
+    }
 }
 
-pub fn create_constraint_set_t(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_v(board), create_t_added_constraint_set(board))
+// Every mine has one or two orthogonally touching mines, and exactly two mines are the ends of the snake
+// with only one. Together with the connectivity constraint this leaves a single path that never touches itself.
+// cells are the undecided cells, cells2 the mines that are already placed.
+fn add_snake_rows(pb:&mut RowProblem, colums:&[Col], lookup:&HashMap<usize, usize>, constraint:&Constraint, width:usize, height:usize){
+    let grid = empty_board(width, height);
+
+    let nodes: Vec<usize> = constraint.cells.iter().chain(constraint.cells2.iter()).cloned().collect();
+    let node_set: HashSet<usize> = nodes.iter().cloned().collect();
+
+    let mine_var = |id:usize| lookup.get(&id).map(|i| colums[*i]);
+
+    let mut ends = vec![];
+
+    for id in nodes {
+        let end = pb.add_integer_column(0., 0..1);
+        ends.push((end, 1.));
+
+        let mut neighbours = vec![];
+        let mut placed = 0.;
+        for other in grid.get_next_to(id, NextToPolicy::XSmall) {
+            if !node_set.contains(&other) {continue};
+            match mine_var(other) {
+                Some(x) => neighbours.push((x, 1.)),
+                None => placed += 1.
+            }
+        }
+        let most = neighbours.len() as f64 + placed;
+
+        match mine_var(id) {
+            Some(x) => {
+                // A mine has 2 - end touching mines, a safe cell is not an end
+                pb.add_row(..=0., vec![(end, 1.), (x, -1.)]);
+
+                let mut upper = neighbours.clone();
+                upper.push((x, most - 2.));
+                upper.push((end, 1.));
+                pb.add_row(..=(most - placed), upper);
+
+                let mut lower = neighbours;
+                lower.push((x, -2.));
+                lower.push((end, 1.));
+                pb.add_row((-placed).., lower);
+            }
+            None => {
+                let mut row = neighbours;
+                row.push((end, 1.));
+                pb.add_row((2. - placed)..=(2. - placed), row);
+            }
+        }
+    }
+
+    pb.add_row(2.0..=2.0, ends);
 }
 
-pub fn create_constraint_set_x_prime(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_minecount(board), create_constraint_set_mini_cross_mines(board))
+// A cell is visible when it and every cell before it in its direction is safe, so visibility is the product
+// of (1 - mine) along the line, built up one cell at a time.
+fn add_eyesight_rows(pb:&mut RowProblem, colums:&[Col], lookup:&HashMap<usize, usize>, constraint:&Constraint, per_direction:[usize; 4]){
+    let mut seen = vec![];
+    let mut offset = 0;
+
+    for count in per_direction {
+        let mut previous = None;
+        for i in offset..offset+count {
+            let x = colums[*lookup.get(&constraint.cells[i]).unwrap()];
+            let visible = pb.add_integer_column(0., 0..1);
+            match previous {
+                None => pb.add_row(1.0..=1.0, vec![(visible, 1.), (x, 1.)]),
+                Some(before) => {
+                    pb.add_row(..=0., vec![(visible, 1.), (before, -1.)]);
+                    pb.add_row(..=1., vec![(visible, 1.), (x, 1.)]);
+                    pb.add_row(0.., vec![(visible, 1.), (before, -1.), (x, 1.)]);
+                }
+            }
+            seen.push((visible, constraint.payload[i] as f64));
+            previous = Some(visible);
+        }
+        offset += count;
+    }
+
+    let value = constraint.value as f64;
+    pb.add_row(value..=value, seen);
 }
 
-fn probe_cell(constraints:&ConstraintSet, probe_id:usize) -> ProbeResult{
+// Builds the model for the given constraints of the set. Only whether a layout exists matters, so there is no objective.
+fn build_problem(constraints:&ConstraintSet, selected:&[&Constraint]) -> (RowProblem, Vec<Col>){
     let mut lookup: HashMap<usize, usize> = HashMap::new();
     for i in 0..constraints.cells.len() {
         lookup.insert( constraints.cells[i], i);
@@ -462,10 +1011,10 @@ fn probe_cell(constraints:&ConstraintSet, probe_id:usize) -> ProbeResult{
     let mut pb = RowProblem::default();
 
     let colums: Vec<_> = (0..constraints.cells.len()).map(
-        |v| pb.add_integer_column(if constraints.cells[v] == probe_id {1.} else {0.}, 0..1)
+        |_| pb.add_integer_column(0., 0..1)
     ).collect();
 
-    for constraint in &constraints.constraints {
+    for constraint in selected {
         let value:f64 = constraint.value as f64;
         match constraint.constrait_type {
             ConstraitType::Equality => {
@@ -498,48 +1047,987 @@ fn probe_cell(constraints:&ConstraintSet, probe_id:usize) -> ProbeResult{
                 cells.push((abs_cell, value*2.0));
                 pb.add_row((value-black_bias)..=(value-black_bias), cells)
             }
+            ConstraitType::SameCount => {
+                let mut cells : Vec<(_, f64)> = constraint.cells.clone().into_iter().map(|id|(colums[*lookup.get(&id).unwrap()], 1.)).collect();
+                cells.extend(constraint.cells2.clone().into_iter().map(|id|(colums[*lookup.get(&id).unwrap()], -1.)));
+                pb.add_row(value..=value, cells)
+            }
+            ConstraitType::Connected(_) | ConstraitType::ConnectedSafe(_) | ConstraitType::ConnectedToEdge(_) => add_connected_rows(&mut pb, &colums, &lookup, constraint, constraints.width, constraints.height),
+            ConstraitType::Dominoes => add_domino_rows(&mut pb, &colums, &lookup, constraint, constraints.width, constraints.height),
+            ConstraitType::Snake => add_snake_rows(&mut pb, &colums, &lookup, constraint, constraints.width, constraints.height),
+            ConstraitType::Eyesight(per_direction) => add_eyesight_rows(&mut pb, &colums, &lookup, constraint, per_direction),
+            ConstraitType::OneOfPatterns => {
+                let choices: Vec<_> = constraint.payload.iter().map(|_| pb.add_integer_column(0., 0..1)).collect();
+                pb.add_row(1.0..=1.0, choices.iter().map(|c| (*c, 1.)).collect::<Vec<(_, f64)>>());
+                for (bit, id) in constraint.cells.iter().enumerate() {
+                    let mut cells : Vec<(_, f64)> = constraint.payload.iter().zip(&choices).filter(|(mask, _)| (*mask >> bit) & 1 == 1).map(|(_, c)| (*c, 1.)).collect();
+                    cells.push((colums[*lookup.get(id).unwrap()], -1.));
+                    pb.add_row(0.0..=0.0, cells)
+                }
+            }
         }
     }
 
-    let max = pb.clone().optimise(Sense::Maximise).solve();
+    (pb, colums)
+}
+
+// The model of a set with every rule in it. Each solve works on a copy, so only the extra rows are added to it.
+struct HighsPreparedSet {
+    problem: RowProblem,
+    colums: Vec<Col>,
+}
+
+impl HighsPreparedSet {
+    // Solves the model and reads the layout from the columns of the cells. Only HiGHS proving that the rows
+    // cannot all hold means there is no layout; stopping on a limit or failing is an error, not a proof.
+    fn solve(&self, model:Result<Model, highs::HighsStatus>) -> Result<Option<Vec<bool>>, SolverError> {
+        let solved = model.and_then(|model| model.try_solve()).map_err(|status| SolverError::Backend(format!("HiGHS failed with {:?}", status)))?;
+
+        match solved.status() {
+            HighsModelStatus::Optimal => Ok(Some(solved.get_solution().columns()[..self.colums.len()].iter().map(|value| *value > 0.5).collect())),
+            HighsModelStatus::Infeasible | HighsModelStatus::UnboundedOrInfeasible => Ok(None),
+            status => Err(SolverError::Backend(format!("HiGHS stopped with {:?}", status)))
+        }
+    }
+}
 
-    assert_eq!(max.status(), HighsModelStatus::Optimal);
+impl PreparedSet for HighsPreparedSet {
+    fn find_layout(&self, extra:&[ExtraRow]) -> Result<Option<Vec<bool>>, SolverError> {
+        let mut pb = self.problem.clone();
 
-    let score = max.get_solution().columns()[*lookup.get(&probe_id).unwrap()];
+        for row in extra {
+            let cells: Vec<(Col, f64)> = row.cells.iter().map(|(i, factor)| (self.colums[*i], *factor as f64)).collect();
+            pb.add_row((row.min as f64)..=(row.max as f64), cells);
+        }
 
-    if score < 0.5 {
-        return ProbeResult::Safe;
+        self.solve(pb.try_optimise(Sense::Maximise))
     }
 
-    let min = pb.optimise(Sense::Minimise).solve();
+    fn mine_count_range(&self) -> Result<Option<(i64, i64)>, SolverError> {
+        let mut pb = self.problem.clone();
+
+        let count = pb.add_integer_column(1., 0.0..=(self.colums.len() as f64));
+        let mut cells : Vec<(Col, f64)> = self.colums.iter().map(|c| (*c, 1.)).collect();
+        cells.push((count, -1.));
+        pb.add_row(0.0..=0.0, cells);
 
-    assert_eq!(min.status(), HighsModelStatus::Optimal);
+        let mut range = [0; 2];
+        for (i, sense) in [Sense::Minimise, Sense::Maximise].into_iter().enumerate() {
+            match self.solve(pb.clone().try_optimise(sense))? {
+                Some(layout) => range[i] = mines_in(&layout) as i64,
+                None => return Ok(None)
+            }
+        }
 
-    let score2 = min.get_solution().columns()[*lookup.get(&probe_id).unwrap()];
+        Ok(Some((range[0], range[1])))
+    }
+}
 
-    if score2 > 0.5 {
-        return ProbeResult::Mine;
+impl SolverBackend for HighsBackend {
+    fn prepare(&self, constraints:&ConstraintSet, selected:&[&Constraint]) -> Box<dyn PreparedSet> {
+        let (problem, colums) = build_problem(constraints, selected);
+        Box::new(HighsPreparedSet { problem, colums })
     }
+}
 
-    return ProbeResult::Unknown;
+fn is_feasible(backend:&dyn SolverBackend, constraints:&ConstraintSet, selected:&Vec<&Constraint>) -> Result<bool, SolverError>{
+    Ok(backend.find_layout(constraints, selected, &[])?.is_some())
 }
 
-pub fn find_known_squares(board:&Board, constaints_building:fn(&Board) -> ConstraintSet) -> KnownSquares{
-    let s = constaints_building(&board);
+// Finds constraints that cannot all hold together, and where dropping any one of them leaves the rest satisfiable.
+// Constraints are dropped one at a time and kept out whenever the rest still conflict, so what is left is irreducible.
+fn find_conflict<'a>(backend:&dyn SolverBackend, constraints:&'a ConstraintSet) -> Result<Option<Vec<&'a Constraint>>, SolverError>{
+    let mut conflict: Vec<&Constraint> = constraints.constraints.iter().collect();
 
-    let mut saves = HashSet::new();
-    let mut mines = HashSet::new();
+    if is_feasible(backend, constraints, &conflict)? {
+        return Ok(None);
+    }
 
+    let mut i = 0;
+    while i < conflict.len() {
+        let mut without = conflict.clone();
+        without.remove(i);
 
-    for i in 0..board.size * board.size{
-        if board[i] == MinesweeperCell::Empty {
-            match probe_cell(&s, i) {
-                ProbeResult::Mine => mines.insert(i),
-                ProbeResult::Safe => saves.insert(i),
-                ProbeResult::Unknown => true
-            };
+        if is_feasible(backend, constraints, &without)? {
+            i += 1;
+        } else {
+            conflict = without;
         }
     }
 
-    return KnownSquares { mines: mines, safe: saves }
-}
\ No newline at end of file
+    Ok(Some(conflict))
+}
+
+fn conflict_error(constraints:&ConstraintSet, conflict:&Vec<&Constraint>) -> SolverError{
+    let mut clue_cells: Vec<usize> = conflict.iter().filter_map(|constraint| constraint.source).collect();
+    clue_cells.sort();
+    clue_cells.dedup();
+
+    SolverError::Infeasible {
+        clue_cells: clue_cells.into_iter().map(|id| (id % constraints.width, id / constraints.width)).collect(),
+        constraints: conflict.iter().map(|constraint| constraint.to_string()).collect(),
+    }
+}
+
+// Marks the value each cell takes in a layout that fits the board
+fn record_layout(seen:&mut [[bool; 2]], layout:&[bool]){
+    for (values_seen, mine) in seen.iter_mut().zip(layout) {
+        values_seen[*mine as usize] = true;
+    }
+}
+
+// A layout that meets the prepared set and the extra rows of any one of the alternatives
+fn find_layout_in_any(prepared:&dyn PreparedSet, alternatives:&[Vec<ExtraRow>]) -> Result<Option<Vec<bool>>, SolverError>{
+    for extra in alternatives {
+        if let Some(layout) = prepared.find_layout(extra)? {
+            return Ok(Some(layout));
+        }
+    }
+    Ok(None)
+}
+
+// Looks for a layout where the cell takes the value no layout found so far gave it. When there is none, the cell is proven.
+fn probe_cell(prepared:&dyn PreparedSet, alternatives:&[Vec<ExtraRow>], seen:&mut [[bool; 2]], index:usize) -> Result<ProbeResult, SolverError>{
+    let (untried, proven) = if seen[index][1] { (false, ProbeResult::Mine) } else { (true, ProbeResult::Safe) };
+
+    let forced: Vec<Vec<ExtraRow>> = alternatives.iter().map(|extra| {
+        let mut forced = extra.clone();
+        forced.push(ExtraRow::fixed(index, untried));
+        forced
+    }).collect();
+
+    match find_layout_in_any(prepared, &forced)? {
+        Some(layout) => {
+            record_layout(seen, &layout);
+            Ok(ProbeResult::Unknown)
+        },
+        None => Ok(proven)
+    }
+}
+
+// Splits the empty cells into groups that the rules link to each other. Cells no rule touches are left out of every group.
+// Rules without any empty cell go in a group of their own, so a contradiction in them is still found.
+fn find_components(constraints:&ConstraintSet, selected:&Vec<&Constraint>) -> Vec<ConstraintSet>{
+    let mut lookup: HashMap<usize, usize> = HashMap::new();
+    for i in 0..constraints.cells.len() {
+        lookup.insert( constraints.cells[i], i);
+    }
+
+    fn root(parent:&mut [usize], mut i:usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut parent: Vec<usize> = (0..constraints.cells.len()).collect();
+    let linked: Vec<Vec<usize>> = selected.iter().map(
+        |constraint| constraint.cells.iter().chain(constraint.cells2.iter()).filter_map(|id| lookup.get(id).cloned()).collect()
+    ).collect();
+
+    for cells in &linked {
+        for pair in cells.windows(2) {
+            let (a, b) = (root(&mut parent, pair[0]), root(&mut parent, pair[1]));
+            parent[a] = b;
+        }
+    }
+
+    let empty_set = || ConstraintSet { cells: vec![], constraints: vec![], width: constraints.width, height: constraints.height };
+
+    let mut group_of_root: HashMap<usize, usize> = HashMap::new();
+    let mut groups: Vec<ConstraintSet> = vec![];
+    let mut without_cells = empty_set();
+
+    for (constraint, cells) in selected.iter().zip(&linked) {
+        if cells.is_empty() {
+            without_cells.constraints.push((*constraint).clone());
+            continue;
+        }
+
+        let group = *group_of_root.entry(root(&mut parent, cells[0])).or_insert_with(|| {
+            groups.push(empty_set());
+            groups.len() - 1
+        });
+        groups[group].constraints.push((*constraint).clone());
+    }
+
+    for i in 0..constraints.cells.len() {
+        if let Some(group) = group_of_root.get(&root(&mut parent, i)) {
+            groups[*group].cells.push(constraints.cells[i]);
+        }
+    }
+
+    if !without_cells.constraints.is_empty() {
+        groups.push(without_cells);
+    }
+
+    groups
+}
+
+// Every layout found while probing shows a value each cell can take, so only cells that have been seen
+// with one value so far need a solve of their own. The mine count of the set has to fall in one of the given ranges,
+// or is left free without them. Returns false when no layout fits at all.
+fn probe_component(prepared:&dyn PreparedSet, constraints:&ConstraintSet, mine_counts:Option<&Vec<(i64, i64)>>, known:&mut KnownSquares) -> Result<bool, SolverError>{
+    let alternatives: Vec<Vec<ExtraRow>> = match mine_counts {
+        Some(ranges) => ranges.iter().map(|(min, max)| vec![ExtraRow::mine_count(constraints.cells.len(), *min, *max)]).collect(),
+        None => vec![vec![]]
+    };
+
+    let first = match find_layout_in_any(prepared, &alternatives)? {
+        Some(layout) => layout,
+        None => return Ok(false)
+    };
+
+    let mut seen = vec![[false; 2]; constraints.cells.len()];
+    record_layout(&mut seen, &first);
+
+    for index in 0..constraints.cells.len() {
+        if seen[index] == [true, true] {
+            continue;
+        }
+
+        match probe_cell(prepared, &alternatives, &mut seen, index)? {
+            ProbeResult::Mine => known.mines.insert(constraints.cells[index]),
+            ProbeResult::Safe => known.safe.insert(constraints.cells[index]),
+            ProbeResult::Unknown => true
+        };
+    }
+
+    Ok(true)
+}
+
+// Every number of mines the cells of the set can hold, as a flag for each count from none up to every cell.
+// None when the rules contradict each other.
+fn achievable_mine_counts(prepared:&dyn PreparedSet, cell_count:usize) -> Result<Option<Vec<bool>>, SolverError>{
+    let (fewest, most) = match prepared.mine_count_range()? {
+        Some(range) => range,
+        None => return Ok(None)
+    };
+
+    let mut counts = vec![false; cell_count + 1];
+    counts[fewest as usize] = true;
+    counts[most as usize] = true;
+    for count in fewest+1..most {
+        counts[count as usize] = prepared.find_layout(&[ExtraRow::mine_count(cell_count, count, count)])?.is_some();
+    }
+
+    Ok(Some(counts))
+}
+
+// Every total that one count from each of the two can add up to
+fn add_counts(a:&[bool], b:&[bool]) -> Vec<bool>{
+    let mut sums = vec![false; a.len() + b.len() - 1];
+    for (i, _) in a.iter().enumerate().filter(|(_, possible)| **possible) {
+        for (j, _) in b.iter().enumerate().filter(|(_, possible)| **possible) {
+            sums[i + j] = true;
+        }
+    }
+    sums
+}
+
+fn has_count(counts:&[bool], count:i64) -> bool {
+    count >= 0 && (count as usize) < counts.len() && counts[count as usize]
+}
+
+// The runs of possible counts, as the lowest and highest count of each run
+fn count_ranges(counts:&[bool]) -> Vec<(i64, i64)>{
+    let mut ranges: Vec<(i64, i64)> = vec![];
+    for (count, _) in counts.iter().enumerate().filter(|(_, possible)| **possible) {
+        match ranges.last_mut() {
+            Some(range) if range.1 + 1 == count as i64 => range.1 = count as i64,
+            _ => ranges.push((count as i64, count as i64))
+        }
+    }
+    ranges
+}
+
+// Groups of cells that no rule links are solved on their own, and only the total mine count ties them together.
+// Each group is only allowed the mine counts that leave a count the other groups and the floating cells can
+// make up exactly, so a proof that rests on how the total is shared out is still found.
+pub fn find_known_squares(board:&Board, constaints_building:impl Fn(&Board) -> ConstraintSet, backend:&dyn SolverBackend) -> Result<KnownSquares, SolverError>{
+    let s = constaints_building(board);
+
+    let conflict = || Err(conflict_error(&s, &find_conflict(backend, &s)?.unwrap_or(vec![])));
+
+    let mine_count = s.constraints.iter().position(|constraint| is_mine_count(&s, constraint));
+    let selected: Vec<&Constraint> = s.constraints.iter().enumerate().filter(|(i, _)| Some(*i) != mine_count).map(|(_, constraint)| constraint).collect();
+
+    let components = find_components(&s, &selected);
+    let in_component: HashSet<usize> = components.iter().flat_map(|component| component.cells.iter().cloned()).collect();
+    let floating: Vec<usize> = s.cells.iter().cloned().filter(|id| !in_component.contains(id)).collect();
+
+    // Each group is built once and only gets the rows of a single probe added after that
+    let prepared: Vec<Box<dyn PreparedSet>> = components.iter().map(|component| backend.prepare(component, &component.constraints.iter().collect::<Vec<_>>())).collect();
+
+    let mut known = KnownSquares { mines: HashSet::new(), safe: HashSet::new() };
+
+    let remaining = match mine_count {
+        Some(i) => s.constraints[i].value,
+        None => {
+            for (component, prepared) in components.iter().zip(&prepared) {
+                if !probe_component(prepared.as_ref(), component, None, &mut known)? {
+                    return conflict();
+                }
+            }
+            return Ok(known);
+        }
+    };
+
+    // With a single group and nothing floating, the group holds every remaining mine
+    if components.len() == 1 && floating.is_empty() {
+        if !probe_component(prepared[0].as_ref(), &components[0], Some(&vec![(remaining, remaining)]), &mut known)? {
+            return conflict();
+        }
+        return Ok(known);
+    }
+
+    let counts = match components.iter().zip(&prepared).map(|(component, prepared)| achievable_mine_counts(prepared.as_ref(), component.cells.len())).collect::<Result<Option<Vec<Vec<bool>>>, SolverError>>()? {
+        Some(counts) => counts,
+        None => return conflict()
+    };
+    let floating_counts = vec![true; floating.len() + 1];
+
+    // Mines the floating cells can hold once the groups have theirs
+    let group_totals = counts.iter().fold(vec![true], |sums, counts| add_counts(&sums, counts));
+    let floating_allowed: Vec<i64> = (0..=floating.len() as i64).filter(|count| has_count(&group_totals, remaining - count)).collect();
+    let (floating_min, floating_max) = match (floating_allowed.first(), floating_allowed.last()) {
+        (Some(min), Some(max)) => (*min, *max),
+        _ => return conflict()
+    };
+
+    for (i, component) in components.iter().enumerate() {
+        let others = counts.iter().enumerate().filter(|(j, _)| *j != i).fold(floating_counts.clone(), |sums, (_, counts)| add_counts(&sums, counts));
+        let allowed = count_ranges(&counts[i].iter().enumerate().map(|(count, possible)| *possible && has_count(&others, remaining - count as i64)).collect::<Vec<_>>());
+
+        if !probe_component(prepared[i].as_ref(), component, Some(&allowed), &mut known)? {
+            return conflict();
+        }
+    }
+
+    if floating_min == floating.len() as i64 {
+        known.mines.extend(floating.iter());
+    } else if floating_max == 0 {
+        known.safe.extend(floating.iter());
+    }
+
+    Ok(known)
+}
+
+// Most layouts of one group of cells that are enumerated, past this the chances for that group only come from the layouts found so far
+const MAX_LAYOUTS: usize = 1000;
+
+// Ways of choosing k of n cells, none when k is out of range
+fn choose(n:usize, k:i64) -> f64 {
+    if k < 0 || k as usize > n {
+        return 0.0;
+    }
+    (0..k as usize).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+// The total mine count is the only rule that touches every empty cell with nothing else on it
+fn is_mine_count(constraints:&ConstraintSet, constraint:&Constraint) -> bool {
+    constraint.source.is_none() && constraint.constrait_type == ConstraitType::Equality && constraint.cells2.is_empty() && constraint.cells.len() == constraints.cells.len()
+}
+
+// Layouts of the cells that rules other than the total mine count say something about. Each one stands for every way
+// of placing the rest of the mines among the floating cells, which only the total mine count touches.
+struct Layouts {
+    floating: Vec<usize>,
+    // Mines left to place in the front and floating cells together, None when the total is not known
+    remaining: Option<i64>,
+    layouts: Vec<Vec<bool>>,
+}
+
+impl Layouts {
+    // Number of layouts of the whole board that this layout of the front stands for
+    fn weight(&self, layout:&[bool]) -> f64 {
+        match self.remaining {
+            Some(remaining) => choose(self.floating.len(), remaining - mines_in(layout) as i64),
+            None => 2f64.powi(self.floating.len() as i32)
+        }
+    }
+}
+
+fn mines_in(layout:&[bool]) -> usize {
+    layout.iter().filter(|mine| **mine).count()
+}
+
+// Finds up to limit layouts of the given columns, asking for one layout at a time and then ruling that exact layout out.
+// The flag says whether every layout was found.
+fn enumerate_group(prepared:&dyn PreparedSet, indices:&[usize], mut extra:Vec<ExtraRow>, limit:usize) -> Result<(Vec<Vec<bool>>, bool), SolverError> {
+    let mut layouts = vec![];
+    loop {
+        let layout: Vec<bool> = match prepared.find_layout(&extra)? {
+            Some(values) => indices.iter().map(|i| values[*i]).collect(),
+            None => return Ok((layouts, true))
+        };
+        if layouts.len() == limit {
+            return Ok((layouts, false));
+        }
+
+        // Every mine in the layout turned safe or safe square turned mine gives a different layout
+        let cut: Vec<(usize, i64)> = indices.iter().zip(&layout).map(|(i, mine)| (*i, if *mine {-1} else {1})).collect();
+        let mines = mines_in(&layout) as i64;
+        layouts.push(layout);
+
+        if cut.is_empty() {
+            return Ok((layouts, true));
+        }
+        extra.push(ExtraRow { min: 1 - mines, max: cut.len() as i64 - mines, cells: cut });
+    }
+}
+
+// Finds up to limit layouts of the front of the whole board at once
+fn enumerate_layouts(backend:&dyn SolverBackend, constraints:&ConstraintSet, limit:usize) -> Result<Layouts, SolverError> {
+    let mine_count = constraints.constraints.iter().position(|constraint| is_mine_count(constraints, constraint));
+    let selected: Vec<&Constraint> = constraints.constraints.iter().enumerate().filter(|(i, _)| Some(*i) != mine_count).map(|(_, constraint)| constraint).collect();
+
+    let constrained: HashSet<usize> = selected.iter().flat_map(|constraint| constraint.cells.iter().chain(constraint.cells2.iter()).cloned()).collect();
+    let front_indices: Vec<usize> = (0..constraints.cells.len()).filter(|i| constrained.contains(&constraints.cells[*i])).collect();
+    let floating: Vec<usize> = constraints.cells.iter().cloned().filter(|id| !constrained.contains(id)).collect();
+
+    let mut extra = vec![];
+
+    // The mines left for the floating cells have to fit in them
+    let remaining = mine_count.map(|i| constraints.constraints[i].value);
+    if let Some(remaining) = remaining {
+        extra.push(ExtraRow { cells: front_indices.iter().map(|i| (*i, 1)).collect(), min: remaining - floating.len() as i64, max: remaining });
+    }
+
+    let prepared = backend.prepare(constraints, &selected);
+    let (layouts, _) = enumerate_group(prepared.as_ref(), &front_indices, extra, limit)?;
+
+    Ok(Layouts { floating, remaining, layouts })
+}
+
+// The layouts found for one group of cells, added up by how many mines they hold
+struct GroupTally {
+    // Layouts holding each number of mines
+    layouts: Vec<f64>,
+    // For each number of mines, how many of the layouts holding it have a mine on each cell of the group
+    mines: Vec<Vec<f64>>,
+}
+
+impl GroupTally {
+    fn new(layouts:&[Vec<bool>], cell_count:usize) -> GroupTally {
+        let mut tally = GroupTally { layouts: vec![0.0; cell_count + 1], mines: vec![vec![0.0; cell_count]; cell_count + 1] };
+        for layout in layouts {
+            let count = mines_in(layout);
+            tally.layouts[count] += 1.0;
+            for (i, _) in layout.iter().enumerate().filter(|(_, mine)| **mine) {
+                tally.mines[count][i] += 1.0;
+            }
+        }
+        tally
+    }
+}
+
+// Layouts of two groups together by how many mines they hold between them
+fn combine_tallies(a:&[f64], b:&[f64]) -> Vec<f64>{
+    let mut sums = vec![0.0; a.len() + b.len() - 1];
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() {
+            sums[i + j] += a * b;
+        }
+    }
+    sums
+}
+
+#[derive(Debug)]
+pub struct MineProbabilities {
+    pub probabilities: HashMap<usize, f64>,
+    // False when a group had more than MAX_LAYOUTS layouts, so its chances only come from the ones found
+    pub complete: bool,
+}
+
+// Chance of each empty cell being a mine, with every layout of mines that fits the board equally likely.
+// Groups of cells that no rule links are enumerated on their own and only tied together by how many mines each
+// one holds, as in find_known_squares, so one group with many layouts does not crowd out the others.
+pub fn find_mine_probabilities(board:&Board, constaints_building:impl Fn(&Board) -> ConstraintSet, backend:&dyn SolverBackend) -> Result<MineProbabilities, SolverError>{
+    let s = constaints_building(board);
+
+    if let Some(conflict) = find_conflict(backend, &s)? {
+        return Err(conflict_error(&s, &conflict));
+    }
+
+    let mine_count = s.constraints.iter().position(|constraint| is_mine_count(&s, constraint));
+    let selected: Vec<&Constraint> = s.constraints.iter().enumerate().filter(|(i, _)| Some(*i) != mine_count).map(|(_, constraint)| constraint).collect();
+
+    let components = find_components(&s, &selected);
+    let in_component: HashSet<usize> = components.iter().flat_map(|component| component.cells.iter().cloned()).collect();
+    let floating: Vec<usize> = s.cells.iter().cloned().filter(|id| !in_component.contains(id)).collect();
+    let remaining = mine_count.map(|i| s.constraints[i].value);
+
+    let mut complete = true;
+    let mut tallies = vec![];
+    for component in &components {
+        let prepared = backend.prepare(component, &component.constraints.iter().collect::<Vec<_>>());
+        // A group never holds more than the mines left
+        let extra = remaining.map(|remaining| vec![ExtraRow::mine_count(component.cells.len(), 0, remaining)]).unwrap_or_default();
+        let (layouts, found_all) = enumerate_group(prepared.as_ref(), &(0..component.cells.len()).collect::<Vec<_>>(), extra, MAX_LAYOUTS)?;
+
+        complete &= found_all;
+        tallies.push(GroupTally::new(&layouts, component.cells.len()));
+    }
+
+    // Ways of placing the rest of the mines among the floating cells once the groups hold front_mines of them,
+    // and the chance of one floating cell being a mine then
+    let rest = |front_mines:usize| match remaining {
+        Some(remaining) => choose(floating.len(), remaining - front_mines as i64),
+        None => 1.0
+    };
+    let floating_share = |front_mines:usize| match remaining {
+        Some(remaining) => (remaining - front_mines as i64) as f64 / floating.len() as f64,
+        None => 0.5
+    };
+
+    let all = tallies.iter().fold(vec![1.0], |sums, tally| combine_tallies(&sums, &tally.layouts));
+    let total_weight: f64 = all.iter().enumerate().map(|(mines, layouts)| layouts * rest(mines)).sum();
+
+    let mut probabilities = HashMap::new();
+    for (g, (component, tally)) in components.iter().zip(&tallies).enumerate() {
+        let others = tallies.iter().enumerate().filter(|(h, _)| *h != g).fold(vec![1.0], |sums, (_, tally)| combine_tallies(&sums, &tally.layouts));
+        // Layouts of the whole board that each layout of the group holding this many mines stands for
+        let weights: Vec<f64> = (0..tally.layouts.len()).map(|mines| others.iter().enumerate().map(|(other_mines, layouts)| layouts * rest(mines + other_mines)).sum()).collect();
+
+        for (i, id) in component.cells.iter().enumerate() {
+            let weight: f64 = tally.mines.iter().zip(&weights).map(|(mines, weight)| mines[i] * weight).sum();
+            probabilities.insert(*id, weight / total_weight);
+        }
+    }
+
+    if !floating.is_empty() {
+        let floating_weight: f64 = all.iter().enumerate().map(|(mines, layouts)| layouts * rest(mines) * floating_share(mines)).sum();
+        for id in &floating {
+            probabilities.insert(*id, floating_weight / total_weight);
+        }
+    }
+
+    Ok(MineProbabilities { probabilities, complete })
+}
+
+#[derive(Copy)]
+#[derive(Clone)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum SolutionCount {
+    Exactly(usize),
+    // There are at least this many, counting stopped at the cap
+    AtLeast(usize),
+}
+
+impl fmt::Display for SolutionCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolutionCount::Exactly(1) => write!(f, "1 solution"),
+            SolutionCount::Exactly(count) => write!(f, "{} solutions", count),
+            SolutionCount::AtLeast(count) => write!(f, "at least {} solutions", count),
+        }
+    }
+}
+
+// Counts the layouts of mines that fit the board, stopping once there are cap of them.
+// A misread board or a wrong rule usually shows up as no solutions or as many.
+pub fn count_solutions(board:&Board, constaints_building:impl Fn(&Board) -> ConstraintSet, backend:&dyn SolverBackend, cap:usize) -> Result<SolutionCount, SolverError>{
+    let s = constaints_building(board);
+
+    // Every layout of the front stands for at least one solution, so more than cap of them are never needed
+    let layouts = enumerate_layouts(backend, &s, cap)?;
+
+    let total: f64 = layouts.layouts.iter().map(|layout| layouts.weight(layout)).sum();
+
+    if total >= cap as f64 {
+        Ok(SolutionCount::AtLeast(cap))
+    } else {
+        Ok(SolutionCount::Exactly(total.round() as usize))
+    }
+}
+
+// The puzzles are made to be solved without guessing, so a correctly read board has exactly one solution
+pub fn has_unique_solution(board:&Board, constaints_building:impl Fn(&Board) -> ConstraintSet, backend:&dyn SolverBackend) -> Result<bool, SolverError>{
+    Ok(count_solutions(board, constaints_building, backend, 2)? == SolutionCount::Exactly(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correct_conflict() {
+        // The 1 in the corner needs a mine next to it, but the two 0s rule out every square it touches
+        let board: Board = "V 3x3 ?\n1 0\n   \n0  \n".parse().unwrap();
+
+        match find_known_squares(&board, create_constraint_set_normal_mines, &HighsBackend) {
+            Err(SolverError::Infeasible { clue_cells, constraints }) => {
+                assert_eq!(clue_cells, vec![(0, 0), (2, 0), (0, 2)]);
+                assert_eq!(constraints.len(), 3);
+            },
+            _ => panic!("the board should be infeasible")
+        }
+
+        let board: Board = "V 3x3 ?\n1 0\n   \n1  \n".parse().unwrap();
+        let known = find_known_squares(&board, create_constraint_set_normal_mines, &HighsBackend).unwrap();
+        assert_eq!(known.mines, HashSet::from([3]));
+        assert_eq!(known.safe, HashSet::from([1, 4, 5, 7]));
+
+        // The 1 already touches the flag, so the second mine can only go where it does not join up with it
+        let board: Board = "C 4x1 2\nF1  \n".parse().unwrap();
+        let variant = board.variant.unwrap();
+        assert!(matches!(find_known_squares(&board, |board| variant.constraint_set(board), &HighsBackend), Err(SolverError::Infeasible { .. })));
+    }
+
+    #[test]
+    fn correct_mine_probabilities() {
+        // One mine is next to the 1 and the other is anywhere among the five squares away from it
+        let board: Board = "V 3x3 2\n1  \n   \n   \n".parse().unwrap();
+        let result = find_mine_probabilities(&board, |board| combine_constraint_sets(create_constraint_set_normal_mines(board), create_constraint_set_minecount(board)), &HighsBackend).unwrap();
+        let probabilities = result.probabilities;
+
+        assert!(result.complete);
+        assert_eq!(probabilities.len(), 8);
+        for id in [1, 3, 4] {
+            assert!((probabilities[&id] - 1.0 / 3.0).abs() < 1e-9);
+        }
+        for id in [2, 5, 6, 7, 8] {
+            assert!((probabilities[&id] - 1.0 / 5.0).abs() < 1e-9);
+        }
+
+        // The second mine has to touch the flagged one, on either side of it
+        let board: Board = "C 5x1 2\n  F  \n".parse().unwrap();
+        let variant = board.variant.unwrap();
+        let probabilities = find_mine_probabilities(&board, |board| variant.constraint_set(board), &HighsBackend).unwrap().probabilities;
+
+        assert_eq!(probabilities.len(), 4);
+        for (id, probability) in [(0, 0.0), (1, 0.5), (3, 0.5), (4, 0.0)] {
+            assert!((probabilities[&id] - probability).abs() < 1e-9);
+        }
+
+        // Each 1 has its mine in one of three squares, and the third mine is in one of the two squares between them
+        let board: Board = "V 5x2 3\n1   1\n     \n".parse().unwrap();
+        for backend in [&HighsBackend as &dyn SolverBackend, &BacktrackingBackend] {
+            let probabilities = find_mine_probabilities(&board, |board| combine_constraint_sets(create_constraint_set_normal_mines(board), create_constraint_set_minecount(board)), backend).unwrap().probabilities;
+
+            for id in [1, 5, 6, 3, 8, 9] {
+                assert!((probabilities[&id] - 1.0 / 3.0).abs() < 1e-9);
+            }
+            for id in [2, 7] {
+                assert!((probabilities[&id] - 0.5).abs() < 1e-9);
+            }
+        }
+
+        // Without the total each group is on its own, and the floating squares are as likely a mine as not
+        let board: Board = "V 5x2 ?\n1   2\n     \n".parse().unwrap();
+        let probabilities = find_mine_probabilities(&board, create_constraint_set_normal_mines, &HighsBackend).unwrap().probabilities;
+        for (id, probability) in [(1, 1.0 / 3.0), (3, 2.0 / 3.0), (2, 0.5)] {
+            assert!((probabilities[&id] - probability).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn correct_group_enumeration() {
+        // Up to two mines in three squares makes seven layouts, which a limit of five cuts short
+        let constraints = ConstraintSet { constraints: vec![], width: 3, height: 1, cells: vec![0, 1, 2] };
+        let prepared = HighsBackend.prepare(&constraints, &[]);
+        let extra = vec![ExtraRow::mine_count(3, 0, 2)];
+
+        let (layouts, complete) = enumerate_group(prepared.as_ref(), &[0, 1, 2], extra.clone(), 10).unwrap();
+        assert!(complete);
+        assert_eq!(layouts.len(), 7);
+        assert_eq!(layouts.iter().collect::<HashSet<_>>().len(), 7);
+
+        let (layouts, complete) = enumerate_group(prepared.as_ref(), &[0, 1, 2], extra, 5).unwrap();
+        assert!(!complete);
+        assert_eq!(layouts.len(), 5);
+    }
+
+    #[test]
+    fn correct_solution_counts() {
+        let with_count = |board:&Board| combine_constraint_sets(create_constraint_set_normal_mines(board), create_constraint_set_minecount(board));
+
+        let board: Board = "V 3x3 2\n1  \n   \n   \n".parse().unwrap();
+        assert_eq!(count_solutions(&board, with_count, &HighsBackend, 100), Ok(SolutionCount::Exactly(15)));
+        assert_eq!(count_solutions(&board, with_count, &HighsBackend, 10), Ok(SolutionCount::AtLeast(10)));
+        assert_eq!(has_unique_solution(&board, with_count, &HighsBackend), Ok(false));
+
+        let board: Board = "V 3x3 1\n1 0\n   \n0  \n".parse().unwrap();
+        assert_eq!(count_solutions(&board, with_count, &HighsBackend, 100), Ok(SolutionCount::Exactly(0)));
+
+        let board: Board = "V 3x1 1\n1  \n".parse().unwrap();
+        assert_eq!(count_solutions(&board, with_count, &HighsBackend, 100), Ok(SolutionCount::Exactly(1)));
+        assert_eq!(has_unique_solution(&board, with_count, &HighsBackend), Ok(true));
+
+        // One mine in every row and column leaves the two diagonals
+        let board: Board = "B 2x2 2\n  \n  \n".parse().unwrap();
+        let variant = board.variant.unwrap();
+        assert_eq!(count_solutions(&board, |board| variant.constraint_set(board), &HighsBackend, 100), Ok(SolutionCount::Exactly(2)));
+    }
+
+    // Gives up on every solve, the way HiGHS does when it stops on a limit
+    struct GivingUpBackend;
+
+    impl PreparedSet for GivingUpBackend {
+        fn find_layout(&self, _extra:&[ExtraRow]) -> Result<Option<Vec<bool>>, SolverError> {
+            Err(SolverError::Backend("time limit reached".to_string()))
+        }
+    }
+
+    impl SolverBackend for GivingUpBackend {
+        fn prepare(&self, _constraints:&ConstraintSet, _selected:&[&Constraint]) -> Box<dyn PreparedSet> {
+            Box::new(GivingUpBackend)
+        }
+    }
+
+    #[test]
+    fn correct_backend_errors() {
+        // A solve that gave up proves nothing, so it is neither a contradiction nor a proven square
+        let board: Board = "V 3x1 1\n1  \n".parse().unwrap();
+        let error = Some(SolverError::Backend("time limit reached".to_string()));
+
+        assert_eq!(find_known_squares(&board, create_constraint_set_normal_mines, &GivingUpBackend).err(), error);
+        assert_eq!(find_mine_probabilities(&board, create_constraint_set_normal_mines, &GivingUpBackend).err(), error);
+        assert_eq!(count_solutions(&board, create_constraint_set_normal_mines, &GivingUpBackend, 100).err(), error);
+    }
+
+    #[test]
+    fn correct_components() {
+        let with_count = |board:&Board| combine_constraint_sets(create_constraint_set_normal_mines(board), create_constraint_set_minecount(board));
+
+        // The 1 takes one mine, and the squares right of the ? only get what the total mine count leaves them
+        let board: Board = "V 7x1 1\n 1 ?   \n".parse().unwrap();
+        let known = find_known_squares(&board, with_count, &HighsBackend).unwrap();
+        assert!(known.mines.is_empty());
+        assert_eq!(known.safe, HashSet::from([4, 5, 6]));
+
+        let board: Board = "V 7x1 4\n 1 ?   \n".parse().unwrap();
+        let known = find_known_squares(&board, with_count, &HighsBackend).unwrap();
+        assert_eq!(known.mines, HashSet::from([4, 5, 6]));
+        assert!(known.safe.is_empty());
+
+        let board: Board = "V 7x1 2\n 1 ? 1 \n".parse().unwrap();
+        let known = find_known_squares(&board, with_count, &HighsBackend).unwrap();
+        assert!(known.mines.is_empty() && known.safe.is_empty());
+
+        let board: Board = "V 7x1 3\n 1 ? 1 \n".parse().unwrap();
+        assert!(find_known_squares(&board, with_count, &HighsBackend).is_err());
+
+        // The first two squares hold no mines or two, so a single mine has to go in the last one
+        let with_pair = |board:&Board| {
+            let pair = Constraint { constrait_type: ConstraitType::SameCount, value: 0, cells: vec![0], cells2: vec![1], payload: vec![], source: None };
+            let last = Constraint { constrait_type: ConstraitType::Maximum, value: 1, cells: vec![2], cells2: vec![], payload: vec![], source: None };
+            let mut set = create_constraint_set_minecount(board);
+            set.constraints.extend([pair, last]);
+            set
+        };
+        let board: Board = "V 3x1 1\n   \n".parse().unwrap();
+        for backend in [&HighsBackend as &dyn SolverBackend, &BacktrackingBackend] {
+            let known = find_known_squares(&board, with_pair, backend).unwrap();
+            assert_eq!(known.mines, HashSet::from([2]));
+            assert_eq!(known.safe, HashSet::from([0, 1]));
+        }
+    }
+
+    #[test]
+    fn correct_balance_without_total() {
+        let with_balance = |board:&Board| combine_constraint_sets(create_constraint_set_minecount(board), create_b_added_constraint_set(board));
+
+        // Three columns and two rows only hold the same number of mines each with every square a mine
+        let board: Board = "B 3x2 ?\nF  \n   \n".parse().unwrap();
+        let known = find_known_squares(&board, with_balance, &HighsBackend).unwrap();
+        assert_eq!(known.mines, HashSet::from([1, 2, 3, 4, 5]));
+        assert!(known.safe.is_empty());
+
+        // Four mines cannot be shared out evenly over three columns
+        let board: Board = "B 3x2 4\nF  \n   \n".parse().unwrap();
+        assert!(find_known_squares(&board, with_balance, &HighsBackend).is_err());
+    }
+
+    #[test]
+    fn correct_triplet_on_thin_boards() {
+        // Two flags in a row leave no room for a third mine next to them
+        let board: Board = "T 3x1 ?\nFF \n".parse().unwrap();
+        let variant = board.variant.unwrap();
+        let known = find_known_squares(&board, |board| variant.constraint_set(board), &HighsBackend).unwrap();
+        assert!(known.mines.is_empty());
+        assert_eq!(known.safe, HashSet::from([2]));
+
+        // Boards narrower or lower than three squares have no lines of three across them
+        for text in ["T 5x1 ?\n     \n", "T 1x5 ?\n \n \n \n \n \n", "T 2x2 ?\n  \n  \n"] {
+            let board: Board = text.parse().unwrap();
+            assert!(find_known_squares(&board, |board| variant.constraint_set(board), &HighsBackend).is_ok());
+        }
+    }
+
+    #[test]
+    fn correct_connected() {
+        // The 1 takes one more mine, and only left of it does the mine touch the flagged one
+        let board: Board = "C 4x1 2\nF 1 \n".parse().unwrap();
+        let variant = board.variant.unwrap();
+
+        for backend in [&HighsBackend as &dyn SolverBackend, &BacktrackingBackend] {
+            let known = find_known_squares(&board, |board| variant.constraint_set(board), backend).unwrap();
+            assert_eq!(known.mines, HashSet::from([1]));
+            assert_eq!(known.safe, HashSet::from([3]));
+        }
+    }
+
+    #[test]
+    fn correct_outside() {
+        // The 1 has a mine above it or left of it, and a mine in the middle would be walled in by revealed squares
+        let board: Board = "O 3x3 ?\n? ?\n? 1\n???\n".parse().unwrap();
+        let variant = board.variant.unwrap();
+
+        for backend in [&HighsBackend as &dyn SolverBackend, &BacktrackingBackend] {
+            let known = find_known_squares(&board, |board| variant.constraint_set(board), backend).unwrap();
+            assert_eq!(known.mines, HashSet::from([1]));
+            assert_eq!(known.safe, HashSet::from([4]));
+        }
+
+        // Revealed squares on both sides of an undecided one are only connected through it
+        let board: Board = "O 3x1 ?\n? ?\n".parse().unwrap();
+        let known = find_known_squares(&board, |board| variant.constraint_set(board), &HighsBackend).unwrap();
+        assert!(known.mines.is_empty());
+        assert_eq!(known.safe, HashSet::from([1]));
+    }
+
+    #[test]
+    fn correct_dual() {
+        // The 0 keeps the square next to it clear, so the flag can only pair with the square on its other side
+        let board: Board = "D 4x1 ?\nF  0\n".parse().unwrap();
+        let variant = board.variant.unwrap();
+
+        for backend in [&HighsBackend as &dyn SolverBackend, &BacktrackingBackend] {
+            let known = find_known_squares(&board, |board| variant.constraint_set(board), backend).unwrap();
+            assert_eq!(known.mines, HashSet::from([1]));
+            assert_eq!(known.safe, HashSet::from([2]));
+        }
+    }
+
+    #[test]
+    fn correct_ring_runs() {
+        assert_eq!(ring_runs(&[false; 8]), Vec::<usize>::new());
+        assert_eq!(ring_runs(&[true; 8]), vec![8]);
+        assert_eq!(ring_runs(&[true, true, false, true, false, false, false, false]), vec![2, 1]);
+        // The run at the end of the ring carries on at its start
+        assert_eq!(ring_runs(&[true, false, false, false, false, true, true, true]), vec![4]);
+    }
+
+    #[test]
+    fn correct_ring_patterns() {
+        // The ring of the middle square goes 0, 1, 2, 5, 8, 7, 6, 3, so its undecided squares are 5, 8, 7 and 6 in that order
+        let board: Board = "W 3x3 ?\n?FF\n?W \n   \nW 1 3\n".parse().unwrap();
+
+        // One more mine next to the two flags, as bitmasks over the undecided squares
+        let constraints = create_ring_pattern_constraints(4, &board, |ring| ring.iter().filter(|mine| **mine).count() == 3);
+        assert_eq!(constraints.len(), 1);
+        assert!(constraints[0].constrait_type == ConstraitType::OneOfPatterns);
+        assert_eq!(constraints[0].cells, vec![5, 8, 7, 6]);
+        assert_eq!(constraints[0].payload, vec![1, 2, 4, 8]);
+
+        // The flags only become the run of three with 5, and then the run of one is 7 or 6 on its own
+        let constraints = create_constraints_for_cell_w(4, &board);
+        assert_eq!(constraints[0].payload, vec![0b0101, 0b1001]);
+
+        // A ring without undecided squares adds nothing
+        let board: Board = "W 3x3 ?\n?F?\n?W?\n???\nW 1\n".parse().unwrap();
+        assert!(create_constraints_for_cell_w(4, &board).is_empty());
+    }
+
+    #[test]
+    fn correct_wall() {
+        // The flags have to grow into the run of three on the right, which 8 then ends
+        let board: Board = "W 3x3 ?\n?FF\n?W \n   \nW 1 3\n".parse().unwrap();
+        let variant = board.variant.unwrap();
+
+        for backend in [&HighsBackend as &dyn SolverBackend, &BacktrackingBackend] {
+            let known = find_known_squares(&board, |board| variant.constraint_set(board), backend).unwrap();
+            assert_eq!(known.mines, HashSet::from([5]));
+            assert_eq!(known.safe, HashSet::from([8]));
+        }
+    }
+
+    #[test]
+    fn correct_partition() {
+        // The flags on 1 and 5 are one group only when 2 joins them, and a mine on 6 would be a group of its own
+        let board: Board = "P 3x3 ?\n F \n?1F\n ? \n".parse().unwrap();
+        let variant = board.variant.unwrap();
+
+        for backend in [&HighsBackend as &dyn SolverBackend, &BacktrackingBackend] {
+            let known = find_known_squares(&board, |board| variant.constraint_set(board), backend).unwrap();
+            assert_eq!(known.mines, HashSet::from([2]));
+            assert_eq!(known.safe, HashSet::from([6]));
+        }
+    }
+
+    #[test]
+    fn correct_eyesight_constraint() {
+        // Looking right from the 3, 2 also uncovers the revealed 3 behind it, and 4 the 1 behind it; looking left there is only 0
+        let board: Board = "E 6x1 ?\n 3 ? 1\n".parse().unwrap();
+        let constraints = create_constraints_for_cell_e(1, &board);
+        assert_eq!(constraints.len(), 1);
+        assert!(constraints[0].constrait_type == ConstraitType::Eyesight([0, 2, 0, 1]));
+        assert_eq!(constraints[0].value, 2);
+        assert_eq!(constraints[0].cells, vec![2, 4, 0]);
+        assert_eq!(constraints[0].payload, vec![2, 2, 1]);
+
+        // Revealed squares before the first undecided one count towards the clue itself, and a mine ends the line
+        let board: Board = "E 5x1 ?\n3? F \n".parse().unwrap();
+        let constraints = create_constraints_for_cell_e(0, &board);
+        assert!(constraints[0].constrait_type == ConstraitType::Eyesight([0, 1, 0, 0]));
+        assert_eq!(constraints[0].value, 1);
+        assert_eq!(constraints[0].cells, vec![2]);
+        assert_eq!(constraints[0].payload, vec![1]);
+    }
+
+    #[test]
+    fn correct_eyesight() {
+        // The 1 sees nothing past its neighbour, so 4 is a mine. The 3 then sees two squares right through 2 or one left
+        // through 0, and only the first adds up
+        let board: Board = "E 6x1 ?\n 3 ? 1\n".parse().unwrap();
+        let variant = board.variant.unwrap();
+
+        for backend in [&HighsBackend as &dyn SolverBackend, &BacktrackingBackend] {
+            let known = find_known_squares(&board, |board| variant.constraint_set(board), backend).unwrap();
+            assert_eq!(known.mines, HashSet::from([0, 4]));
+            assert_eq!(known.safe, HashSet::from([2]));
+        }
+    }
+
+    #[test]
+    fn correct_knight() {
+        // From the corner the 2 only reaches 5 and 7, and with 7 a mine the 1 has none left for 3
+        let board: Board = "K 3x3 ?\n2 1\n   \n   \n".parse().unwrap();
+        let variant = board.variant.unwrap();
+
+        for backend in [&HighsBackend as &dyn SolverBackend, &BacktrackingBackend] {
+            let known = find_known_squares(&board, |board| variant.constraint_set(board), backend).unwrap();
+            assert_eq!(known.mines, HashSet::from([5, 7]));
+            assert_eq!(known.safe, HashSet::from([3]));
+        }
+    }
+
+    #[test]
+    fn correct_longest_run() {
+        // The longest run of four can only be the flags carried on through 5 and 8, which 7 then has to end
+        let board: Board = "W' 3x3 ?\n?FF\n?4 \n   \n".parse().unwrap();
+        let variant = board.variant.unwrap();
+
+        for backend in [&HighsBackend as &dyn SolverBackend, &BacktrackingBackend] {
+            let known = find_known_squares(&board, |board| variant.constraint_set(board), backend).unwrap();
+            assert_eq!(known.mines, HashSet::from([5, 8]));
+            assert_eq!(known.safe, HashSet::from([7]));
+        }
+    }
+
+    #[test]
+    fn correct_snake() {
+        // The flags are two of the three mines, so the square between them has to be the one joining them
+        let board: Board = "S 3x3 3\nF F\n ??\n???\n".parse().unwrap();
+        let variant = board.variant.unwrap();
+
+        for backend in [&HighsBackend as &dyn SolverBackend, &BacktrackingBackend] {
+            let known = find_known_squares(&board, |board| variant.constraint_set(board), backend).unwrap();
+            assert_eq!(known.mines, HashSet::from([1]));
+            assert_eq!(known.safe, HashSet::from([3]));
+        }
+    }
+}
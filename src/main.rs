@@ -1,5 +1,9 @@
-use algorithms::Solver;
 use construct_board::VisionType;
+use plus_linear_solvers::Variant;
+use mine_counts::MineCountTable;
+use calibration::Calibration;
+use cli::{Command, Options};
+use error::SolverError;
 
 mod board_image_getter;
 mod image_to_square;
@@ -9,114 +13,269 @@ mod algorithms;
 mod game_inputs;
 mod linear_programming_solvers;
 mod plus_linear_solvers;
+mod mine_counts;
+mod screen_grid;
+mod calibration;
+mod cli;
+mod error;
+mod backtracking_solver;
 use std::{thread, time};
+use std::collections::HashSet;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-    /*
-    let board_size = 8;
-
-    let img = board_image_getter::get_square_image(&board_image_getter::get_whole_window(), 8, 4, 3);
-    board_image_getter::save_image(img);
-    
-    */
-    let board_size = 8;
+    let options = match cli::parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
 
-    let mut j = 0;
+    let mut mine_counts = MineCountTable::standard();
 
-    while j < 10 {
+    // Mine counts for sizes and variants beyond the built in table
+    if let Ok(text) = std::fs::read_to_string("mine_counts.txt") {
+        if let Err(message) = mine_counts.add_from_text(&text) {
+            eprintln!("Unable to parse mine_counts.txt: {}", message);
+            std::process::exit(1);
+        }
+    }
 
-        let mut i=0;
+    let result = match &options.command {
+        Command::Play => play(&options, &mine_counts),
+        Command::Image(path) => solve_image(path, &options, &mine_counts),
+        Command::Text(path) => solve_text(path, &options, &mine_counts),
+        Command::Benchmark(paths) => benchmark(paths, &options, &mine_counts),
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        },
+    };
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
 
-        //solve_generic(board_size, VisionType::ChessSmall, plus_linear_solvers::create_constraint_set_tn);
+fn variant_or_vanilla(options:&Options) -> Variant {
+    options.variant.unwrap_or(Variant::from_code("V").unwrap())
+}
 
-        solve_b(board_size);
+fn play(options:&Options, mine_counts:&MineCountTable) -> Result<(), Box<dyn std::error::Error>>{
+    let variant = variant_or_vanilla(options);
+
+    // --calibrate finds the board in the game window and saves where it is for the next runs
+    let (calibration, detected_size) = if options.calibrate {
+        let (calibration, width, height) = Calibration::load("calibration.txt").unwrap_or(Calibration::standard())
+            .detect(&board_image_getter::get_whole_window()?)
+            .ok_or(SolverError::Capture("no board grid found in the game window".to_string()))?;
+        calibration.save("calibration.txt")?;
+        (calibration, Some((width, height)))
+    } else {
+        (Calibration::load("calibration.txt").unwrap_or(Calibration::standard()), None)
+    };
+
+    let (width, height) = options.size.or(detected_size).unwrap_or((8, 8));
+    let total_mines = options.mines.or(mine_counts.get(&variant, width, height));
+    warn_missing_counter_templates();
+
+    for _ in 0..options.puzzles {
+        solve_generic(&calibration, (width, height), Some(variant), variant.vision_type(), total_mines, options, |board| variant.constraint_set(board))?;
+
+        // Nothing was clicked, so the next read would show the same board
+        if options.dry_run {
+            break;
+        }
 
-        game_inputs::click_next_puzzle();
-        thread::sleep(time::Duration::from_millis(500));    
-        j += 1;
+        game_inputs::click_next_puzzle()?;
+        thread::sleep(time::Duration::from_millis(500));
     }
 
-    /*
-    let board_size = 8;
-    let mut i=0;
-    while i<100{
+    Ok(())
+}
 
-        let board = construct_board::get_board(board_size);
+fn solve_image(path:&str, options:&Options, mine_counts:&MineCountTable) -> Result<(), Box<dyn std::error::Error>>{
+    let variant = variant_or_vanilla(options);
+    let calibration = Calibration::load("calibration.txt").unwrap_or(Calibration::standard());
 
-        println!("{}", board);
+    // A saved screenshot may not be of the board the calibration was made for, so its grid is found first when it can be
+    let (calibration, width, height) = match options.size {
+        Some((width, height)) => (calibration, width, height),
+        None => calibration.detect(&board_image_getter::load_screenshot(path)?).unwrap_or((calibration, 8, 8))
+    };
+    let total_mines = options.mines.or(mine_counts.get(&variant, width, height));
 
-        let solver = algorithms::vanilla_solver();
+    warn_missing_counter_templates();
+    let mut board = construct_board::get_board_from_image(path, &calibration, width, height, variant.vision_type(), total_mines)?;
+    board.variant = Some(variant);
 
-        let res = solver.get_known_squares(&board);
+    println!("{}", board);
+    warn_missing_total(&board);
 
-        algorithms::display_known_squares(&res, board_size);
+    let res = linear_programming_solvers::find_known_squares(&board, |board| variant.constraint_set(board), options.backend.solver())?;
 
-        if !game_inputs::click_known_cells(res, board_size) {break};
+    algorithms::display_known_squares(&res, width, height);
+    report_solutions(&board, variant, options);
+    Ok(())
+}
 
-        thread::sleep(time::Duration::from_millis(500));
+// Reads a board in the text format. The options override the variant and mine count of the header,
+// and a board without a mine count falls back to the mine count table.
+fn read_text_board(path:&str, options:&Options, mine_counts:&MineCountTable) -> Result<board::Board, String>{
+    let text = std::fs::read_to_string(path).map_err(|err| format!("Unable to read {}: {}", path, err))?;
+    let mut board: board::Board = text.parse().map_err(|err| format!("Unable to parse {}: {}", path, err))?;
 
-        i += 1;
-    }
-    */
-}
+    let variant = options.variant.or(board.variant).unwrap_or(Variant::from_code("V").unwrap());
+    board.variant = Some(variant);
+    board.total_mines = options.mines.or(board.total_mines).or(mine_counts.get(&variant, board.width, board.height));
 
-fn solve_v(board_size:usize){
-    solve_generic(board_size, VisionType::Normal, linear_programming_solvers::create_constraint_set_v)
+    Ok(board)
 }
 
-fn solve_q(board_size:usize){
-    solve_generic(board_size, VisionType::Normal, linear_programming_solvers::create_constraint_set_q)
-}
+fn solve_text(path:&str, options:&Options, mine_counts:&MineCountTable) -> Result<(), Box<dyn std::error::Error>>{
+    let board = read_text_board(path, options, mine_counts)?;
+    let variant = board.variant.unwrap();
 
-fn solve_m(board_size:usize){
-    solve_generic(board_size, VisionType::ChessBig, linear_programming_solvers::create_constraint_set_m)
-}
+    println!("{}", board);
+    warn_missing_total(&board);
 
-fn solve_l(board_size:usize){
-    solve_generic(board_size, VisionType::UpToNine, linear_programming_solvers::create_constraint_set_l)
-}
+    let res = linear_programming_solvers::find_known_squares(&board, |board| variant.constraint_set(board), options.backend.solver())?;
 
-fn solve_b(board_size:usize){
-    solve_generic(board_size, VisionType::Normal, linear_programming_solvers::create_constraint_set_b)
+    algorithms::display_known_squares(&res, board.width, board.height);
+    report_solutions(&board, variant, options);
+    Ok(())
 }
 
-fn solve_n(board_size:usize){
-    solve_generic(board_size, VisionType::ChessSmall, linear_programming_solvers::create_constraint_set_n)
+// Without a total the mine count is left out of the constraints, so fewer squares can be proven
+fn warn_missing_total(board:&board::Board){
+    if board.total_mines.is_none() {
+        let variant = board.variant.map_or("?".to_string(), |variant| variant.to_string());
+        println!("No total mine count is known for {} {}x{}, solving without it. Pass --mines or add it to mine_counts.txt", variant, board.width, board.height);
+    }
 }
 
-fn solve_x(board_size:usize){
-    solve_generic(board_size, VisionType::Normal, linear_programming_solvers::create_constraint_set_x)
+// Without digit templates the mine counter is never read, so there is nothing to check the mine count against
+fn warn_missing_counter_templates(){
+    if image_to_square::get_img_counter_pairs().is_empty() {
+        println!("No mine counter templates in images/counter, so the mine counter is not read or checked against the mine count");
+    }
 }
 
-fn solve_t(board_size:usize){
-    solve_generic(board_size, VisionType::Normal, linear_programming_solvers::create_constraint_set_t)
+// Most solutions counted for a board, past this it only reports that there are at least this many
+const SOLUTION_CAP: usize = 100;
+
+fn report_solutions(board:&board::Board, variant:Variant, options:&Options){
+    let constraint_set = |board:&board::Board| variant.constraint_set(board);
+
+    // Most boards are unique, and telling that only needs two solutions looked for instead of the whole count
+    let count = match linear_programming_solvers::has_unique_solution(board, constraint_set, options.backend.solver()) {
+        Ok(true) => Ok(None),
+        Ok(false) => linear_programming_solvers::count_solutions(board, constraint_set, options.backend.solver(), SOLUTION_CAP).map(Some),
+        Err(err) => Err(err),
+    };
+
+    match count {
+        Ok(None) => println!("The board has 1 solution"),
+        Ok(Some(count)) => println!("The board has {}, so a square may be misread or the variant or mine count is wrong", count),
+        Err(err) => println!("Unable to count the solutions: {}", err),
+    }
 }
 
-fn solve_x_prime(board_size:usize){
-    solve_generic(board_size, VisionType::UpToFour, linear_programming_solvers::create_constraint_set_x_prime)
+fn benchmark(paths:&Vec<String>, options:&Options, mine_counts:&MineCountTable) -> Result<(), Box<dyn std::error::Error>>{
+    let mut total = time::Duration::ZERO;
+
+    for path in paths {
+        let board = read_text_board(path, options, mine_counts)?;
+        let variant = board.variant.unwrap();
+        warn_missing_total(&board);
+
+        let start = time::Instant::now();
+        let res = linear_programming_solvers::find_known_squares(&board, |board| variant.constraint_set(board), options.backend.solver());
+        let elapsed = start.elapsed();
+        total += elapsed;
+
+        // A board that cannot be solved is reported with the rest instead of ending the benchmark
+        match res {
+            Ok(res) => println!("{}: {} {}x{}, {} safe, {} mines in {} ms", path, variant, board.width, board.height, res.safe.len(), res.mines.len(), elapsed.as_millis()),
+            Err(err) => println!("{}: {} {}x{}, {} in {} ms", path, variant, board.width, board.height, err, elapsed.as_millis()),
+        }
+    }
+
+    println!("{} boards in {} ms", paths.len(), total.as_millis());
+    Ok(())
 }
 
-fn solve_generic(board_size:usize, vision_type:VisionType, constrain_set_maker:fn(&board::Board) -> linear_programming_solvers::ConstraintSet){
-    let mut i=0;
-    while i<100{
+fn best_guess(board:&board::Board, constrain_set_maker:impl Fn(&board::Board) -> linear_programming_solvers::ConstraintSet, options:&Options) -> Result<algorithms::KnownSquares, SolverError>{
+    let probabilities = linear_programming_solvers::find_mine_probabilities(board, constrain_set_maker, options.backend.solver())?;
+    if !probabilities.complete {
+        println!("Too many layouts to go through them all, so the chances only come from the first ones found");
+    }
 
-        let board = construct_board::get_board(board_size, vision_type);
+    let (cell, probability) = probabilities.probabilities.into_iter()
+        .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+        .ok_or(SolverError::Stuck)?;
+    println!("Guessing {}, {} with a {:.0}% chance of a mine", cell % board.width, cell / board.width, probability * 100.0);
 
-        println!("{}", board);
+    Ok(algorithms::KnownSquares { mines: HashSet::new(), safe: HashSet::from([cell]) })
+}
 
-        let res = linear_programming_solvers::find_known_squares(&board, constrain_set_maker);
+// A misread square or a board read halfway through an animation shows up as an unrecognised cell, as constraints
+// that cannot be met or as a mine counter that disagrees, so the board is read again a few times before giving up
+const MAX_MISREADS: usize = 3;
 
-        algorithms::display_known_squares(&res, board_size);
+fn solve_generic(calibration:&Calibration, size:(usize, usize), variant:Option<Variant>, vision_type:VisionType, total_mines:Option<usize>, options:&Options, constrain_set_maker:impl Fn(&board::Board) -> linear_programming_solvers::ConstraintSet) -> Result<(), SolverError>{
+    let (width, height) = size;
+    let mut i=0;
+    let mut misreads=0;
+    while i<100{
 
-        if !game_inputs::click_known_cells(&res, board_size) {panic!("I cannot solve this puzzle")};
+        let read = construct_board::get_board(calibration, width, height, vision_type, total_mines).and_then(|mut board| {
+            board.variant = variant;
+            println!("{}", board);
+            if i == 0 && misreads == 0 {
+                warn_missing_total(&board);
+            }
+
+            let res = linear_programming_solvers::find_known_squares(&board, &constrain_set_maker, options.backend.solver())?;
+            Ok((board, res))
+        });
+
+        let (board, res) = match read {
+            Err(err @ (SolverError::UnrecognisedCell { .. } | SolverError::Infeasible { .. } | SolverError::MineCountMismatch { .. })) if misreads < MAX_MISREADS => {
+                println!("{}, reading the board again", err);
+                misreads += 1;
+                thread::sleep(time::Duration::from_millis(options.turn_delay));
+                continue;
+            },
+            read => read?
+        };
+
+        // Nothing can be proven, so the square least likely to be a mine is opened instead
+        let res = if res.safe.is_empty() && res.mines.is_empty() {
+            best_guess(&board, &constrain_set_maker, options)?
+        } else {
+            res
+        };
+
+        algorithms::display_known_squares(&res, width, height);
+
+        if options.dry_run {
+            break;
+        }
+
+        game_inputs::click_known_cells(&res, calibration, width, height, options.click_delay)?;
 
         if board::cells_left(&board) == res.mines.len() + res.safe.len() {
             break;
         }
 
-        thread::sleep(time::Duration::from_millis(70));
+        thread::sleep(time::Duration::from_millis(options.turn_delay));
 
         i += 1;
     }
-}
\ No newline at end of file
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use crate::plus_linear_solvers::{Variant, MineCountPolicy};
+
+// Total number of mines on a board, by variant and by width and height.
+// A variant without its own entry falls back to the count for its mine count policy.
+// Sizes the game does not ship with are configured in a text file, one entry per line:
+//   standard 10x10 40
+//   balance 9x9 27
+//   QW 8x8 24
+// The first word is a mine count policy or a variant code. Lines starting with # are ignored.
+pub struct MineCountTable {
+    defaults: HashMap<(MineCountPolicy, usize, usize), usize>,
+    counts: HashMap<(Variant, usize, usize), usize>,
+}
+
+impl MineCountTable {
+    pub fn standard() -> MineCountTable {
+        let mut defaults = HashMap::new();
+
+        for (size, total) in [(5, 10), (6, 14), (7, 20), (8, 26)] {
+            defaults.insert((MineCountPolicy::Standard, size, size), total);
+        }
+        for (size, total) in [(5, 10), (6, 12), (7, 21), (8, 24)] {
+            defaults.insert((MineCountPolicy::Balance, size, size), total);
+        }
+
+        MineCountTable { defaults, counts: HashMap::new() }
+    }
+
+    pub fn set(&mut self, variant:Variant, width:usize, height:usize, total:usize) {
+        self.counts.insert((variant, width, height), total);
+    }
+
+    pub fn set_default(&mut self, policy:MineCountPolicy, width:usize, height:usize, total:usize) {
+        self.defaults.insert((policy, width, height), total);
+    }
+
+    pub fn add_from_text(&mut self, text:&str) -> Result<(), String> {
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || format!("Invalid mine count on line {}: {}", number + 1, line);
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.len() != 3 {
+                return Err(invalid());
+            }
+
+            let (width, height) = words[1].split_once('x').ok_or_else(invalid)?;
+            let width = width.parse().map_err(|_| invalid())?;
+            let height = height.parse().map_err(|_| invalid())?;
+            let total = words[2].parse().map_err(|_| invalid())?;
+
+            let (policy, variant) = match words[0].to_lowercase().as_str() {
+                "standard" => (MineCountPolicy::Standard, None),
+                "balance" => (MineCountPolicy::Balance, None),
+                code => {
+                    let variant = Variant::from_code(code).ok_or_else(invalid)?;
+                    (variant.mine_rule.mine_count(), Some(variant))
+                }
+            };
+
+            // Balanced boards have the same number of mines in every row and every column
+            if policy == MineCountPolicy::Balance && (total % width != 0 || total % height != 0) {
+                return Err(format!("{}, {} mines cannot be shared out evenly over the rows and columns", invalid(), total));
+            }
+
+            match variant {
+                Some(variant) => self.set(variant, width, height, total),
+                None => self.set_default(policy, width, height, total),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, variant:&Variant, width:usize, height:usize) -> Option<usize> {
+        self.counts.get(&(*variant, width, height))
+            .or(self.defaults.get(&(variant.mine_rule.mine_count(), width, height)))
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correct_mine_counts() {
+        let mut table = MineCountTable::standard();
+        let vanilla = Variant::from_code("V").unwrap();
+        let balance = Variant::from_code("BX").unwrap();
+
+        assert_eq!(table.get(&vanilla, 8, 8), Some(26));
+        assert_eq!(table.get(&balance, 6, 6), Some(12));
+        assert_eq!(table.get(&vanilla, 9, 9), None);
+
+        table.set(vanilla, 8, 8, 30);
+        assert_eq!(table.get(&vanilla, 8, 8), Some(30));
+        assert_eq!(table.get(&Variant::from_code("Q").unwrap(), 8, 8), Some(26));
+    }
+
+    #[test]
+    fn correct_mine_counts_from_text() {
+        let mut table = MineCountTable::standard();
+        let text = "# larger boards\nstandard 10x10 40\n\nbalance 9x12 36\nqw 8x8 24\n";
+
+        assert_eq!(table.add_from_text(text), Ok(()));
+        assert_eq!(table.get(&Variant::from_code("X").unwrap(), 10, 10), Some(40));
+        assert_eq!(table.get(&Variant::from_code("BE").unwrap(), 9, 12), Some(36));
+        assert_eq!(table.get(&Variant::from_code("QW").unwrap(), 8, 8), Some(24));
+        assert_eq!(table.get(&Variant::from_code("Q").unwrap(), 8, 8), Some(26));
+
+        assert!(table.add_from_text("standard 10 40").is_err());
+        assert!(table.add_from_text("Z 8x8 20").is_err());
+        assert!(table.add_from_text("balance 9x9 30").is_err());
+        assert!(table.add_from_text("BE 9x12 27").is_err());
+    }
+}
@@ -1,86 +1,233 @@
+use std::fmt;
+
 use crate::linear_programming_solvers::{*};
 use crate::board::Board;
-
-//qmlbnxt
-
-pub fn create_constraint_set_qm(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_m(board), create_q_added_constraint_set(board))
-}
-
-pub fn create_constraint_set_ql(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_l(board), create_q_added_constraint_set(board))
-}
-
-// QW
-
-pub fn create_constraint_set_qn(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_n(board), create_q_added_constraint_set(board))
+use crate::construct_board::VisionType;
+
+// A variant is one rule about where the mines can go and one rule about what the clues mean.
+// Each rule adds its own constraints, so every pair from the game's variant grid can be built from its code.
+
+#[derive(Copy)]
+#[derive(Clone)]
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub enum MineRule {
+    Vanilla,
+    Quad,
+    Connected,
+    Triplet,
+    Outside,
+    Dual,
+    Snake,
+    Balance,
 }
 
-pub fn create_constraint_set_qx(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_x(board), create_q_added_constraint_set(board))
+#[derive(Copy)]
+#[derive(Clone)]
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub enum ClueRule {
+    Vanilla,
+    Multiple,
+    Liar,
+    Wall,
+    Negation,
+    Cross,
+    Partition,
+    Eyesight,
+    MiniCross,
+    Knight,
+    LongestRun,
 }
 
-// QP
-
-// QE
-
-// 
-// All of C
-// 
-
-pub fn create_constraint_set_tm(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_m(board), create_t_added_constraint_set(board))
+#[derive(Copy)]
+#[derive(Clone)]
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub enum MineCountPolicy {
+    Standard,
+    Balance,
 }
 
-pub fn create_constraint_set_tl(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_l(board), create_t_added_constraint_set(board))
+#[derive(Copy)]
+#[derive(Clone)]
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct Variant {
+    pub mine_rule: MineRule,
+    pub clue_rule: ClueRule,
 }
 
-// TW
-
-pub fn create_constraint_set_tn(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_n(board), create_t_added_constraint_set(board))
+impl MineRule {
+    pub fn from_code(code:&str) -> Option<MineRule> {
+        match code {
+            "V" => Some(MineRule::Vanilla),
+            "Q" => Some(MineRule::Quad),
+            "C" => Some(MineRule::Connected),
+            "T" => Some(MineRule::Triplet),
+            "O" => Some(MineRule::Outside),
+            "D" => Some(MineRule::Dual),
+            "S" => Some(MineRule::Snake),
+            "B" => Some(MineRule::Balance),
+            _ => None
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            MineRule::Vanilla => "V",
+            MineRule::Quad => "Q",
+            MineRule::Connected => "C",
+            MineRule::Triplet => "T",
+            MineRule::Outside => "O",
+            MineRule::Dual => "D",
+            MineRule::Snake => "S",
+            MineRule::Balance => "B",
+        }
+    }
+
+    fn added_constraints(&self) -> Option<fn(&Board) -> ConstraintSet> {
+        match self {
+            MineRule::Vanilla => None,
+            MineRule::Quad => Some(create_q_added_constraint_set),
+            MineRule::Connected => Some(create_c_added_constraint_set),
+            MineRule::Triplet => Some(create_t_added_constraint_set),
+            MineRule::Outside => Some(create_o_added_constraint_set),
+            MineRule::Dual => Some(create_d_added_constraint_set),
+            MineRule::Snake => Some(create_s_added_constraint_set),
+            MineRule::Balance => Some(create_b_added_constraint_set),
+        }
+    }
+
+    pub fn mine_count(&self) -> MineCountPolicy {
+        match self {
+            MineRule::Balance => MineCountPolicy::Balance,
+            _ => MineCountPolicy::Standard
+        }
+    }
 }
 
-pub fn create_constraint_set_tx(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(create_constraint_set_x(board), create_t_added_constraint_set(board))
+impl ClueRule {
+    pub fn from_code(code:&str) -> Option<ClueRule> {
+        match code {
+            "V" => Some(ClueRule::Vanilla),
+            "M" => Some(ClueRule::Multiple),
+            "L" => Some(ClueRule::Liar),
+            "W" => Some(ClueRule::Wall),
+            "N" => Some(ClueRule::Negation),
+            "X" => Some(ClueRule::Cross),
+            "P" => Some(ClueRule::Partition),
+            "E" => Some(ClueRule::Eyesight),
+            "X'" => Some(ClueRule::MiniCross),
+            "K" => Some(ClueRule::Knight),
+            "W'" => Some(ClueRule::LongestRun),
+            _ => None
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ClueRule::Vanilla => "V",
+            ClueRule::Multiple => "M",
+            ClueRule::Liar => "L",
+            ClueRule::Wall => "W",
+            ClueRule::Negation => "N",
+            ClueRule::Cross => "X",
+            ClueRule::Partition => "P",
+            ClueRule::Eyesight => "E",
+            ClueRule::MiniCross => "X'",
+            ClueRule::Knight => "K",
+            ClueRule::LongestRun => "W'",
+        }
+    }
+
+    fn constraints(&self) -> fn(&Board) -> ConstraintSet {
+        match self {
+            ClueRule::Vanilla => create_constraint_set_normal_mines,
+            ClueRule::Multiple => create_constraint_set_multiple_mines,
+            ClueRule::Liar => create_constraint_set_liar_mines,
+            ClueRule::Wall => create_constraint_set_wall_mines,
+            ClueRule::Negation => create_constraint_set_negation_mines,
+            ClueRule::Cross => create_constraint_set_cross_mines,
+            ClueRule::Partition => create_constraint_set_partition_mines,
+            ClueRule::Eyesight => create_constraint_set_eyesight_mines,
+            ClueRule::MiniCross => create_constraint_set_mini_cross_mines,
+            ClueRule::Knight => create_constraint_set_knight_mines,
+            ClueRule::LongestRun => create_constraint_set_longest_run_mines,
+        }
+    }
+
+    pub fn vision_type(&self) -> VisionType {
+        match self {
+            ClueRule::Vanilla => VisionType::Normal,
+            ClueRule::Multiple => VisionType::ChessBig,
+            ClueRule::Liar => VisionType::UpToNine,
+            ClueRule::Wall => VisionType::Wall,
+            ClueRule::Negation => VisionType::ChessSmall,
+            ClueRule::Cross => VisionType::Normal,
+            ClueRule::Partition => VisionType::UpToFour,
+            ClueRule::Eyesight => VisionType::UpToEleven,
+            ClueRule::MiniCross => VisionType::UpToFour,
+            ClueRule::Knight => VisionType::Normal,
+            ClueRule::LongestRun => VisionType::Normal,
+        }
+    }
 }
 
-// TP
+impl Variant {
+    // A code is a mine rule followed by a clue rule, where either can be left out when it is vanilla: V, Q, X', QW, CE
+    pub fn from_code(code:&str) -> Option<Variant> {
+        let code = code.trim().to_uppercase();
 
-// TE
+        if let Some(clue_rule) = ClueRule::from_code(&code) {
+            return Some(Variant { mine_rule: MineRule::Vanilla, clue_rule });
+        }
 
-// 
-// All of O
-// 
+        let first = code.chars().next()?;
+        let mine_rule = MineRule::from_code(&code[..first.len_utf8()])?;
+        let rest = &code[first.len_utf8()..];
+        let clue_rule = if rest.is_empty() {ClueRule::Vanilla} else {ClueRule::from_code(rest)?};
 
-// 
-// All of D
-// 
+        Some(Variant { mine_rule, clue_rule })
+    }
 
-// 
-// All of S
-// 
+    pub fn vision_type(&self) -> VisionType {
+        self.clue_rule.vision_type()
+    }
 
-pub fn create_constraint_set_bm(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(combine_constraint_sets(create_constraint_set_multiple_mines(board), create_constraint_set_minecount_b(board)), create_b_added_constraint_set(board))
-}
+    pub fn constraint_set(&self, board:&Board) -> ConstraintSet {
+        let set = combine_constraint_sets(create_constraint_set_minecount(board), self.clue_rule.constraints()(board));
 
-pub fn create_constraint_set_bl(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(combine_constraint_sets(create_constraint_set_liar_mines(board), create_constraint_set_minecount_b(board)), create_b_added_constraint_set(board))
+        match self.mine_rule.added_constraints() {
+            Some(added) => combine_constraint_sets(set, added(board)),
+            None => set
+        }
+    }
 }
 
-// TW
-
-pub fn create_constraint_set_bn(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(combine_constraint_sets(create_constraint_set_negation_mines(board), create_constraint_set_minecount_b(board)), create_b_added_constraint_set(board))
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.mine_rule, self.clue_rule) {
+            (MineRule::Vanilla, clue_rule) => write!(f, "{}", clue_rule.code()),
+            (mine_rule, ClueRule::Vanilla) => write!(f, "{}", mine_rule.code()),
+            (mine_rule, clue_rule) => write!(f, "{}{}", mine_rule.code(), clue_rule.code()),
+        }
+    }
 }
 
-pub fn create_constraint_set_bx(board:&Board) -> ConstraintSet {
-    combine_constraint_sets(combine_constraint_sets(create_constraint_set_cross_mines(board), create_constraint_set_minecount_b(board)), create_b_added_constraint_set(board))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correct_variant_codes() {
+        assert_eq!(Variant::from_code("V"), Some(Variant { mine_rule: MineRule::Vanilla, clue_rule: ClueRule::Vanilla }));
+        assert_eq!(Variant::from_code("Q"), Some(Variant { mine_rule: MineRule::Quad, clue_rule: ClueRule::Vanilla }));
+        assert_eq!(Variant::from_code("X'"), Some(Variant { mine_rule: MineRule::Vanilla, clue_rule: ClueRule::MiniCross }));
+        assert_eq!(Variant::from_code("qw"), Some(Variant { mine_rule: MineRule::Quad, clue_rule: ClueRule::Wall }));
+        assert_eq!(Variant::from_code("CE"), Some(Variant { mine_rule: MineRule::Connected, clue_rule: ClueRule::Eyesight }));
+        assert_eq!(Variant::from_code("BW'"), Some(Variant { mine_rule: MineRule::Balance, clue_rule: ClueRule::LongestRun }));
+        assert_eq!(Variant::from_code("MQ"), None);
+        assert_eq!(Variant::from_code(""), None);
+
+        for code in ["V", "Q", "X'", "QW", "CE", "BW'", "SK"] {
+            assert_eq!(Variant::from_code(code).unwrap().to_string(), code);
+        }
+    }
 }
-
-// TP
-
-// TE
\ No newline at end of file
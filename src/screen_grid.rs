@@ -0,0 +1,61 @@
+// Where the squares of a board are on the screen.
+// The game centres the board and shrinks the squares once the board no longer fits in the area an 8x8 board takes up,
+// so the position of every square follows from the centre of the grid, the square pitch and the largest board extent.
+
+#[derive(Copy)]
+#[derive(Clone)]
+#[derive(Debug, PartialEq)]
+pub struct ScreenGrid {
+    pub top_left: (i32,i32),
+    pub pitch: (i32,i32),
+}
+
+impl ScreenGrid {
+    pub fn centered(center:(i32,i32), max_pitch:(i32,i32), max_extent:(i32,i32), width:usize, height:usize) -> ScreenGrid {
+        let squares = i32::try_from(width.max(height)).unwrap();
+        let pitch = (max_pitch.0.min(max_extent.0 / squares), max_pitch.1.min(max_extent.1 / squares));
+
+        let width = i32::try_from(width).unwrap();
+        let height = i32::try_from(height).unwrap();
+
+        ScreenGrid {
+            top_left: (center.0 - width * pitch.0 / 2, center.1 - height * pitch.1 / 2),
+            pitch,
+        }
+    }
+
+    pub fn square_origin(&self, square_x:usize, square_y:usize) -> (i32,i32) {
+        (self.top_left.0 + i32::try_from(square_x).unwrap() * self.pitch.0,
+         self.top_left.1 + i32::try_from(square_y).unwrap() * self.pitch.1)
+    }
+
+    pub fn square_center(&self, square_x:usize, square_y:usize) -> (i32,i32) {
+        let origin = self.square_origin(square_x, square_y);
+        (origin.0 + self.pitch.0 / 2, origin.1 + self.pitch.1 / 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correct_square_origins() {
+        let center = (974, 639);
+        let pitch = (92, 92);
+        let extent = (736, 736);
+
+        assert_eq!(ScreenGrid::centered(center, pitch, extent, 8, 8).square_origin(0, 0), (606, 271));
+        assert_eq!(ScreenGrid::centered(center, pitch, extent, 7, 7).square_origin(0, 0), (652, 317));
+        assert_eq!(ScreenGrid::centered(center, pitch, extent, 6, 6).square_origin(1, 2), (790, 547));
+        assert_eq!(ScreenGrid::centered(center, pitch, extent, 8, 5).square_origin(0, 0), (606, 409));
+
+        let large = ScreenGrid::centered(center, pitch, extent, 10, 10);
+        assert_eq!(large.pitch, (73, 73));
+        assert_eq!(large.square_origin(0, 0), (609, 274));
+
+        let clicks = ScreenGrid::centered((948, 617), (88, 88), (704, 704), 8, 8);
+        assert_eq!(clicks.square_center(0, 0), (640, 309));
+        assert_eq!(clicks.square_center(7, 7), (1256, 925));
+    }
+}